@@ -0,0 +1,38 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::state::AppState;
+
+/// Log method, path, status and latency of every request at info level,
+/// except for paths in `ORQOS_ACCESS_LOG_EXCLUDE`.
+///
+/// For WebSocket routes the latency covers the upgrade only.
+pub(crate) async fn access_log(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_owned();
+    if state.config.access_log_exclude.contains(&path) {
+        return next.run(req).await;
+    }
+
+    let method = req.method().clone();
+    let started = Instant::now();
+    let resp = next.run(req).await;
+
+    tracing::info!(
+        target: "orqos::access",
+        %method,
+        path,
+        status = resp.status().as_u16(),
+        latency_ms = started.elapsed().as_secs_f64() * 1000.0,
+        "request"
+    );
+    resp
+}
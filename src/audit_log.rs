@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use axum::{
+    extract::FromRequestParts,
+    extract::{MatchedPath, Query, RawPathParams, Request, State},
+    http::{HeaderMap, Method, StatusCode, Uri},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+
+use crate::state::AppState;
+
+/// Header an authenticating proxy sets to the caller's identity. Orqos has
+/// no auth of its own, so this is only as trustworthy as whatever sits in
+/// front of it.
+const IDENTITY_HEADER: &str = "x-forwarded-user";
+
+enum Sink {
+    Stdout,
+    File(File),
+}
+
+/// Mutating routes that are read-only in effect, or (exec, passthrough)
+/// write their own record.
+const NOT_AUDITED: &[&str] = &[
+    "/containers/{id}/exec",
+    "/containers/{id}/read-file",
+    "/containers/{id}/list-dir",
+    "/docker/{method}",
+];
+
+/// Append-only JSON-lines record of every exec a client runs and every
+/// change it makes (`ORQOS_AUDIT_LOG`: a file path, `-`/unset for stdout,
+/// `off` to disable).
+///
+/// Only byte counts of exec output are kept, never the output itself.
+pub struct AuditLog {
+    sink: Option<Mutex<Sink>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExecRecord<'a> {
+    pub container: &'a str,
+    pub cmd: &'a [String],
+    pub user: Option<&'a str>,
+    /// `rest`, `rest-detached` or `ws`
+    pub transport: &'static str,
+    /// Caller identity from `X-Forwarded-User`, if a proxy set it
+    pub actor: Option<String>,
+    /// `None` if the command didn't finish, was detached, or was refused
+    /// (see `error`)
+    pub exit_code: Option<i64>,
+    pub duration_ms: u64,
+    pub stdout_bytes: usize,
+    pub stderr_bytes: usize,
+    /// Output was cut off at `ORQOS_MAX_EXEC_OUTPUT`, so the counts are lower bounds
+    pub truncated: bool,
+    pub error: Option<String>,
+}
+
+impl<'a> ExecRecord<'a> {
+    /// A record for a command that hasn't run (yet); fill in the outcome.
+    pub fn new(
+        container: &'a str,
+        cmd: &'a [String],
+        user: Option<&'a str>,
+        transport: &'static str,
+        actor: Option<String>,
+    ) -> Self {
+        Self {
+            container,
+            cmd,
+            user,
+            transport,
+            actor,
+            exit_code: None,
+            duration_ms: 0,
+            stdout_bytes: 0,
+            stderr_bytes: 0,
+            truncated: false,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangeRecord {
+    pub actor: Option<String>,
+    /// Method and route, e.g. `POST /containers/{id}/stop`
+    pub action: String,
+    /// The route's path parameters (container, image, stack, ...), `/`-joined;
+    /// `None` for collection routes such as `POST /containers`
+    pub resource: Option<String>,
+    /// `ok` or `error`, from `status`
+    pub result: &'static str,
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+impl AuditLog {
+    pub fn from_env() -> io::Result<Self> {
+        let sink = match std::env::var("ORQOS_AUDIT_LOG").as_deref().map(str::trim) {
+            Err(_) | Ok("" | "-") => Some(Sink::Stdout),
+            Ok(v) if v.eq_ignore_ascii_case("off") => None,
+            Ok(path) => Some(Sink::File(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            )),
+        };
+        Ok(Self {
+            sink: sink.map(Mutex::new),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    pub fn exec(&self, record: &ExecRecord<'_>) {
+        self.write("exec", record);
+    }
+
+    pub fn change(&self, record: &ChangeRecord) {
+        self.write("change", record);
+    }
+
+    /// Write one record as a single line. Failures are logged, not returned:
+    /// whatever is being recorded already happened.
+    fn write(&self, event: &str, record: &impl Serialize) {
+        let Some(sink) = &self.sink else { return };
+
+        let mut line = serde_json::json!({
+            "ts": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "event": event,
+        });
+        if let (Some(obj), Ok(serde_json::Value::Object(fields))) =
+            (line.as_object_mut(), serde_json::to_value(record))
+        {
+            obj.extend(fields);
+        }
+        let mut bytes = line.to_string().into_bytes();
+        bytes.push(b'\n');
+
+        // One write_all per record keeps lines whole under concurrent execs
+        let mut sink = sink.lock().unwrap_or_else(|e| e.into_inner());
+        let res = match &mut *sink {
+            Sink::Stdout => io::stdout().lock().write_all(&bytes),
+            Sink::File(f) => f.write_all(&bytes),
+        };
+        if let Err(e) = res {
+            tracing::error!("failed to write audit record: {e}");
+        }
+    }
+}
+
+/// Record every request to a mutating route (any method but GET, HEAD and
+/// OPTIONS) as a `change`, with the status it got. `plan=true` dry runs
+/// change nothing and are skipped.
+///
+/// For streaming responses (push, create-stream) the status only covers
+/// the start of the stream.
+pub(crate) async fn audit_mutations(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_owned());
+    let audited = state.audit.is_enabled()
+        && !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        && route.as_deref().is_some_and(|r| !NOT_AUDITED.contains(&r))
+        && !is_plan(req.uri());
+    if !audited {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let resource = RawPathParams::from_request_parts(&mut parts, &())
+        .await
+        .ok()
+        .map(|params| params.iter().map(|(_, v)| v).collect::<Vec<_>>().join("/"))
+        .filter(|r| !r.is_empty());
+    let actor = identity(&parts.headers);
+    let action = format!("{} {}", parts.method, route.unwrap_or_default());
+
+    let started = Instant::now();
+    let resp = next.run(Request::from_parts(parts, body)).await;
+    let status = resp.status();
+
+    state.audit.change(&ChangeRecord {
+        actor,
+        action,
+        resource,
+        result: result_of(status),
+        status: status.as_u16(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+    resp
+}
+
+/// `ok` for 2xx/3xx, `error` otherwise.
+pub fn result_of(status: StatusCode) -> &'static str {
+    if status.is_success() || status.is_redirection() {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+/// Whether the query string asks for a dry run (`plan=true`).
+fn is_plan(uri: &Uri) -> bool {
+    Query::<HashMap<String, String>>::try_from_uri(uri)
+        .is_ok_and(|q| q.get("plan").is_some_and(|v| v == "true"))
+}
+
+/// The caller identity to record for a request.
+pub fn identity(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(IDENTITY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_record_is_one_json_line_without_output() {
+        let path = std::env::temp_dir().join(format!("orqos-audit-{}.log", std::process::id()));
+        let log = AuditLog {
+            sink: Some(Mutex::new(Sink::File(File::create(&path).unwrap()))),
+        };
+        let cmd = vec!["cat".to_string(), "/etc/secret".to_string()];
+        log.exec(&ExecRecord {
+            container: "web",
+            cmd: &cmd,
+            user: None,
+            transport: "rest",
+            actor: Some("alice".into()),
+            exit_code: Some(0),
+            duration_ms: 12,
+            stdout_bytes: 42,
+            stderr_bytes: 0,
+            truncated: false,
+            error: None,
+        });
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written.lines().count(), 1);
+        let v: serde_json::Value = serde_json::from_str(written.trim_end()).unwrap();
+        assert_eq!(v["event"], "exec");
+        assert_eq!(v["container"], "web");
+        assert_eq!(v["actor"], "alice");
+        assert_eq!(v["stdout_bytes"], 42);
+        assert!(v["ts"].is_string());
+    }
+
+    #[test]
+    fn plan_requests_are_recognised() {
+        let plan = |u: &str| is_plan(&u.parse().unwrap());
+        assert!(plan("/containers/stop?label=a%3Db&plan=true"));
+        assert!(!plan("/containers/stop?plan=false"));
+        assert!(!plan("/containers/stop"));
+    }
+}
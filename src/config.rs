@@ -0,0 +1,174 @@
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use regex::Regex;
+
+use crate::redact::sensitive_key_regex;
+
+/// Operator-tunable settings, read once from the environment at startup.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Upper bound (seconds) applied to the `t` of stop requests.
+    pub max_stop_timeout: u64,
+    /// Max bytes captured per stream (stdout/stderr) by the REST exec.
+    pub max_exec_output: usize,
+    /// Docker label keys copied onto `/metrics` series as extra dimensions.
+    pub metric_labels: Vec<String>,
+    /// Capacity of the Docker events broadcast ring buffer.
+    pub event_buffer: usize,
+    /// How long `GET /containers` results are reused (0 disables caching).
+    pub container_cache_ttl: Duration,
+    /// Mount Swagger UI and `/api/openapi.json` (`ORQOS_SWAGGER=off` disables).
+    pub swagger_enabled: bool,
+    /// Where Swagger UI is served, e.g. `/swagger`.
+    pub swagger_path: String,
+    /// Host directory `env_file` paths must live under; unset disables them.
+    pub env_file_base: Option<PathBuf>,
+    /// If non-empty, the only binaries the exec API may run.
+    pub exec_allow: Vec<String>,
+    /// Binaries the exec API refuses to run.
+    pub exec_deny: Vec<String>,
+    /// Attempts (including the first) for idempotent Docker reads, 1–10.
+    pub docker_retries: u32,
+    /// Max request body (bytes) for JSON control endpoints (create, stop,
+    /// exec, …): every route without a limit of its own.
+    pub max_body_bytes: usize,
+    /// Max request body (bytes) accepted by the write-file routes.
+    pub max_upload_bytes: usize,
+    /// Max decompressed size (bytes) of a `gzip+base64` write-file payload.
+    pub max_write_bytes: usize,
+    /// Max size (bytes) of a file read out of a container, after any gunzip;
+    /// also caps the archive `list-dir` downloads.
+    pub max_read_bytes: usize,
+    /// Request paths left out of the access log (probes, scrapes).
+    pub access_log_exclude: Vec<String>,
+    /// Env/label keys whose values are masked in responses, compiled from
+    /// the `ORQOS_SENSITIVE_KEYS` globs. `None` when `ORQOS_REDACT_ENV=off`.
+    pub sensitive_keys: Option<Regex>,
+    /// Max `ports` entries accepted by a create request.
+    pub max_ports: usize,
+    /// Host interface published ports bind to when a mapping has no
+    /// `host_ip`: loopback unless opted into public binding.
+    pub default_bind_ip: IpAddr,
+    /// How long an `Idempotency-Key` on create is remembered.
+    pub idempotency_ttl: Duration,
+    /// Run the stats poller (`ORQOS_METRICS=off` disables it, and with it
+    /// every stats and metrics endpoint's data).
+    pub metrics_enabled: bool,
+    /// Methods reachable through `POST /docker/{method}`; empty disables it.
+    pub docker_passthrough: Vec<String>,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self {
+            max_stop_timeout: env_or("ORQOS_MAX_STOP_TIMEOUT", 60),
+            max_exec_output: env_or("ORQOS_MAX_EXEC_OUTPUT", 4 * 1024 * 1024),
+            metric_labels: env_list("ORQOS_METRIC_LABELS"),
+            event_buffer: env_or("ORQOS_EVENT_BUFFER", 100).max(1),
+            container_cache_ttl: Duration::from_millis(env_or(
+                "ORQOS_CONTAINER_CACHE_TTL_MS",
+                1000,
+            )),
+            swagger_enabled: !env_off("ORQOS_SWAGGER"),
+            swagger_path: swagger_path(),
+            env_file_base: env::var_os("ORQOS_ENV_FILE_BASE")
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from),
+            exec_allow: env_list("ORQOS_EXEC_ALLOW"),
+            exec_deny: env_list("ORQOS_EXEC_DENY"),
+            docker_retries: env_or("ORQOS_DOCKER_RETRIES", 3).clamp(1, 10),
+            max_body_bytes: env_or("ORQOS_MAX_BODY_BYTES", 1024 * 1024),
+            max_upload_bytes: env_or("ORQOS_MAX_UPLOAD_BYTES", 16 * 1024 * 1024),
+            max_write_bytes: env_or("ORQOS_MAX_WRITE_BYTES", 100 * 1024 * 1024),
+            max_read_bytes: env_or("ORQOS_MAX_READ_BYTES", 100 * 1024 * 1024),
+            // Set but empty logs everything
+            access_log_exclude: match env::var("ORQOS_ACCESS_LOG_EXCLUDE") {
+                Ok(_) => env_list("ORQOS_ACCESS_LOG_EXCLUDE"),
+                Err(_) => vec!["/metrics".into(), "/healthz".into(), "/readyz".into()],
+            },
+            sensitive_keys: sensitive_keys(),
+            max_ports: env_or("ORQOS_MAX_PORTS", 64),
+            default_bind_ip: default_bind_ip(),
+            idempotency_ttl: Duration::from_secs(env_or("ORQOS_IDEMPOTENCY_TTL_SECS", 3600)),
+            metrics_enabled: !env_off("ORQOS_METRICS"),
+            docker_passthrough: env_list("ORQOS_DOCKER_PASSTHROUGH"),
+        }
+    }
+}
+
+/// Parse `key` from the environment, falling back to `default` when it is
+/// unset or unparseable (the latter is logged).
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match env::var(key) {
+        Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+            tracing::warn!("ignoring invalid value {raw:?} for {key}");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Whether `key` is explicitly switched off (`off`, `false`, `0` or `no`).
+fn env_off(key: &str) -> bool {
+    matches!(
+        env::var(key)
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str(),
+        "off" | "false" | "0" | "no"
+    )
+}
+
+/// Redaction is on unless `ORQOS_REDACT_ENV` is off; the globs default to
+/// `*_TOKEN,*_PASSWORD,*_KEY`.
+fn sensitive_keys() -> Option<Regex> {
+    if env_off("ORQOS_REDACT_ENV") {
+        return None;
+    }
+    let globs = match env::var("ORQOS_SENSITIVE_KEYS") {
+        Ok(_) => env_list("ORQOS_SENSITIVE_KEYS"),
+        Err(_) => vec!["*_TOKEN".into(), "*_PASSWORD".into(), "*_KEY".into()],
+    };
+    sensitive_key_regex(&globs)
+}
+
+/// `ORQOS_DEFAULT_BIND_IP` if set, else all interfaces with
+/// `ORQOS_PUBLIC_PORTS=true`, else `127.0.0.1`.
+fn default_bind_ip() -> IpAddr {
+    let public: bool = env_or("ORQOS_PUBLIC_PORTS", false);
+    let fallback = if public {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    };
+    env_or("ORQOS_DEFAULT_BIND_IP", fallback)
+}
+
+/// `ORQOS_SWAGGER_PATH`, normalised to a leading `/` and no trailing one.
+fn swagger_path() -> String {
+    let raw = env::var("ORQOS_SWAGGER_PATH").unwrap_or_default();
+    let trimmed = raw.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        "/swagger".into()
+    } else {
+        format!("/{trimmed}")
+    }
+}
+
+/// Read `key` as a comma-separated list, dropping blank items.
+fn env_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
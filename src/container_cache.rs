@@ -0,0 +1,141 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use bollard::models::ContainerSummary;
+use dashmap::DashMap;
+use tokio::sync::Mutex;
+
+/// Most distinct queries cached at once. Keys come from client query
+/// strings, so without a bound every new filter combination would stay
+/// forever; past this, uncached queries go straight to the daemon.
+const MAX_SLOTS: usize = 256;
+
+/// (fetched at, generation at fetch time, listing)
+type Slot = Arc<Mutex<Option<(Instant, u64, Arc<Vec<ContainerSummary>>)>>>;
+
+/// Short-lived cache for `list_containers` results, keyed by the query.
+///
+/// Concurrent requests for the same key share a single daemon round-trip:
+/// the first caller fetches while holding the slot lock and everyone queued
+/// behind it is served the fresh value.
+///
+/// Entries also expire early when the generation counter moves past the one
+/// they were fetched under; the event fan-out bumps it on container
+/// lifecycle events. Stale entries are dropped on invalidation and when
+/// the map fills up (see [`MAX_SLOTS`]).
+pub struct ContainerListCache {
+    ttl: Duration,
+    generation: AtomicU64,
+    slots: DashMap<String, Slot>,
+}
+
+impl ContainerListCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            generation: AtomicU64::new(0),
+            slots: DashMap::new(),
+        }
+    }
+
+    /// Whether caching is on at all (and thus wants lifecycle events).
+    pub fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        key: String,
+        fetch: F,
+    ) -> Result<Arc<Vec<ContainerSummary>>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<ContainerSummary>, E>>,
+    {
+        if !self.enabled() {
+            return fetch().await.map(Arc::new);
+        }
+
+        if !self.slots.contains_key(&key) && self.slots.len() >= MAX_SLOTS {
+            self.prune();
+            if self.slots.len() >= MAX_SLOTS {
+                return fetch().await.map(Arc::new);
+            }
+        }
+
+        // Clone the slot out so the DashMap shard isn't held across awaits.
+        let slot = self.slots.entry(key).or_default().clone();
+        let mut guard = slot.lock().await;
+
+        if let Some((at, generation, ref value)) = *guard {
+            if at.elapsed() < self.ttl && generation == self.generation.load(Ordering::Acquire) {
+                return Ok(value.clone());
+            }
+        }
+
+        // Read the generation *before* fetching so an event racing the fetch
+        // still invalidates what we store.
+        let generation = self.generation.load(Ordering::Acquire);
+
+        // Errors aren't cached; the next caller simply retries.
+        let value = Arc::new(fetch().await?);
+        *guard = Some((Instant::now(), generation, value.clone()));
+        Ok(value)
+    }
+
+    /// Invalidate every cached listing (e.g. after a container changed state).
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.prune();
+    }
+
+    /// Drop every entry that is expired or from an older generation. Slots
+    /// that are locked (being fetched or read) are kept.
+    fn prune(&self) {
+        let generation = self.generation.load(Ordering::Acquire);
+        self.slots.retain(|_, slot| match slot.try_lock() {
+            Ok(entry) => entry
+                .as_ref()
+                .is_some_and(|(at, g, _)| at.elapsed() < self.ttl && *g == generation),
+            Err(_) => true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn fill(cache: &ContainerListCache, key: String) {
+        cache
+            .get_or_fetch(key, || async { Ok::<_, ()>(Vec::new()) })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalidate_drops_entries() {
+        let cache = ContainerListCache::new(Duration::from_secs(60));
+        for i in 0..10 {
+            fill(&cache, format!("q{i}")).await;
+        }
+        assert_eq!(cache.slots.len(), 10);
+        cache.invalidate();
+        assert_eq!(cache.slots.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn slots_are_bounded() {
+        let cache = ContainerListCache::new(Duration::from_secs(60));
+        for i in 0..MAX_SLOTS + 50 {
+            fill(&cache, format!("q{i}")).await;
+        }
+        assert_eq!(cache.slots.len(), MAX_SLOTS);
+    }
+}
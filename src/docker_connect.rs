@@ -0,0 +1,163 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use bollard::{ClientVersion, Docker, API_DEFAULT_VERSION};
+use sha2::{Digest, Sha256};
+
+/// Read/write timeout (seconds) for daemon connections.
+const TIMEOUT: u64 = 120;
+
+/// Where the daemon listens when nothing says otherwise.
+const LOCAL_SOCKET: &str = "unix:///var/run/docker.sock";
+
+/// Connect to the Docker daemon, picking the endpoint the way the CLI does:
+///
+/// 1. `ORQOS_DOCKER_SOCKET` (a socket path, `unix://…`, `tcp://…`,
+///    `https://…` or `ssh://…`)
+/// 2. `DOCKER_HOST` (with `DOCKER_TLS_VERIFY`/`DOCKER_CERT_PATH` for TLS)
+/// 3. the active context: `DOCKER_CONTEXT`, else `currentContext` in
+///    `$DOCKER_CONFIG/config.json` (default `~/.docker`)
+/// 4. the platform's default socket
+///
+/// Only the endpoint is chosen here; nothing is dialled until first use.
+/// Every path speaks `ORQOS_DOCKER_API_VERSION` if set, so older daemons
+/// don't reject requests as "client version too new".
+pub(crate) fn connect() -> Result<Docker> {
+    let pinned = pinned_api_version()?;
+    let version = pinned.as_ref().unwrap_or(API_DEFAULT_VERSION);
+
+    if let Some(host) = env::var("ORQOS_DOCKER_SOCKET")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+    {
+        tracing::info!(host, "Docker endpoint from ORQOS_DOCKER_SOCKET");
+        return connect_to(host.trim(), version);
+    }
+
+    if let Ok(host) = env::var("DOCKER_HOST") {
+        tracing::info!("Docker endpoint from DOCKER_HOST");
+        return connect_to(&host, version).context("connecting via DOCKER_HOST");
+    }
+
+    if let Some((context, host)) = context_host()? {
+        tracing::info!(context, host, "Docker endpoint from context");
+        return connect_to(&host, version).with_context(|| format!("Docker context {context:?}"));
+    }
+
+    connect_to(LOCAL_SOCKET, version)
+        .context("no Docker socket found; set ORQOS_DOCKER_SOCKET, DOCKER_HOST or a Docker context")
+}
+
+/// `ORQOS_DOCKER_API_VERSION`, e.g. `1.41`; unset means bollard's default.
+fn pinned_api_version() -> Result<Option<ClientVersion>> {
+    let Some(raw) = env::var("ORQOS_DOCKER_API_VERSION")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    parse_api_version(&raw)
+        .map(Some)
+        .with_context(|| format!("invalid ORQOS_DOCKER_API_VERSION {raw:?} (expected e.g. 1.41)"))
+}
+
+fn parse_api_version(raw: &str) -> Option<ClientVersion> {
+    let (major, minor) = raw.trim().trim_start_matches('v').split_once('.')?;
+    Some(ClientVersion {
+        major_version: major.parse().ok()?,
+        minor_version: minor.parse().ok()?,
+    })
+}
+
+/// Build a client for `host`, as the CLI would: `tcp://` goes over TLS when
+/// `DOCKER_TLS_VERIFY` is set, with the client cert, key and CA taken from
+/// `DOCKER_CERT_PATH` (default: the Docker config dir).
+fn connect_to(host: &str, version: &ClientVersion) -> Result<Docker> {
+    let docker = if host.starts_with("https://") || (host.starts_with("tcp://") && tls_verify()) {
+        let certs = env::var_os("DOCKER_CERT_PATH")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .or_else(docker_config_dir)
+            .context("DOCKER_TLS_VERIFY is set but there is no DOCKER_CERT_PATH or HOME")?;
+        Docker::connect_with_ssl(
+            host,
+            &certs.join("key.pem"),
+            &certs.join("cert.pem"),
+            &certs.join("ca.pem"),
+            TIMEOUT,
+            version,
+        )
+        .with_context(|| format!("TLS client certificates in {}", certs.display()))?
+    } else if host.starts_with("tcp://") || host.starts_with("http://") {
+        Docker::connect_with_http(host, TIMEOUT, version)?
+    } else if host.starts_with("unix://") || host.starts_with('/') {
+        Docker::connect_with_unix(host, TIMEOUT, version)?
+    } else if host.starts_with("ssh://") {
+        Docker::connect_with_ssh(host, TIMEOUT, version)?
+    } else {
+        bail!(
+            "unsupported Docker endpoint {host:?} (expected a socket path, unix://, tcp://, https:// or ssh://)"
+        );
+    };
+    Ok(docker)
+}
+
+/// `DOCKER_TLS_VERIFY`: like the CLI, any non-empty value turns it on.
+fn tls_verify() -> bool {
+    env::var_os("DOCKER_TLS_VERIFY").is_some_and(|v| !v.is_empty())
+}
+
+fn docker_config_dir() -> Option<PathBuf> {
+    env::var_os("DOCKER_CONFIG")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".docker")))
+}
+
+/// `(name, host)` of the active context, or `None` for the built-in
+/// `default` context.
+fn context_host() -> Result<Option<(String, String)>> {
+    let Some(dir) = docker_config_dir() else {
+        return Ok(None);
+    };
+
+    let name = match env::var("DOCKER_CONTEXT") {
+        Ok(name) if !name.trim().is_empty() => name.trim().to_owned(),
+        _ => fs::read(dir.join("config.json"))
+            .ok()
+            .and_then(|raw| serde_json::from_slice::<serde_json::Value>(&raw).ok())
+            .and_then(|cfg| cfg["currentContext"].as_str().map(str::to_owned))
+            .unwrap_or_default(),
+    };
+    if name.is_empty() || name == "default" {
+        return Ok(None);
+    }
+
+    // The CLI stores each context under the hex SHA-256 of its name.
+    let meta = dir
+        .join("contexts/meta")
+        .join(format!("{:x}", Sha256::digest(name.as_bytes())))
+        .join("meta.json");
+    let raw = fs::read(&meta)
+        .with_context(|| format!("Docker context {name:?} not found ({})", meta.display()))?;
+    let meta: serde_json::Value =
+        serde_json::from_slice(&raw).with_context(|| format!("parsing {}", meta.display()))?;
+
+    match meta["Endpoints"]["docker"]["Host"].as_str() {
+        Some(host) => Ok(Some((name, host.to_owned()))),
+        None => bail!("Docker context {name:?} has no docker endpoint"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_version_parses() {
+        let v = parse_api_version("1.41").unwrap();
+        assert_eq!((v.major_version, v.minor_version), (1, 41));
+        assert!(parse_api_version("v1.24").is_some());
+        assert!(parse_api_version("1").is_none());
+        assert!(parse_api_version("1.x").is_none());
+    }
+}
@@ -0,0 +1,35 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use tokio::{spawn, task::JoinHandle, time::sleep};
+
+use crate::state::AppState;
+
+/// Spawns a background task that pings the Docker daemon until it answers,
+/// then flips [`AppState::docker_ready`] and exits.
+///
+/// Used when Orqos starts before `dockerd` (systemd/k8s ordering races):
+/// the HTTP server comes up regardless and `/readyz` reports 503 until then.
+pub(crate) fn spawn_docker_probe(state: Arc<AppState>) -> JoinHandle<()> {
+    spawn(async move {
+        let mut attempt: u32 = 0;
+
+        loop {
+            match state.docker.version().await {
+                Ok(v) => {
+                    tracing::info!("Connected to Docker {:?}", v.version);
+                    state.docker_ready.store(true, Ordering::Release);
+                    return;
+                }
+                Err(err) => {
+                    let backoff = Duration::from_secs(2u64.pow(attempt.min(5)));
+                    tracing::warn!(?err, ?backoff, "Docker still unavailable—retrying");
+                    attempt += 1;
+                    sleep(backoff).await;
+                }
+            }
+        }
+    })
+}
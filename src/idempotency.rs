@@ -0,0 +1,78 @@
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::http::StatusCode;
+use dashmap::DashMap;
+use tokio::sync::Mutex;
+
+/// (stored at, SHA-256 of the request body, result)
+type Slot<T> = Arc<Mutex<Option<(Instant, [u8; 32], T)>>>;
+
+/// Results of recent non-idempotent requests, keyed by the client's
+/// `Idempotency-Key`, so a retried request gets the original answer instead
+/// of repeating the side effect.
+///
+/// Requests sharing a key are serialised on the slot lock: a retry that
+/// races the original waits for it and is then served its result. Only
+/// successes are stored; after an error the key can be used again.
+pub struct IdempotencyCache<T> {
+    ttl: Duration,
+    slots: DashMap<String, Slot<T>>,
+}
+
+impl<T: Clone> IdempotencyCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            slots: DashMap::new(),
+        }
+    }
+
+    /// Run `op` unless `key` already has a live result, which is returned
+    /// instead. Reusing a key with a different body is a 422.
+    pub async fn get_or_run<F, Fut>(
+        &self,
+        key: String,
+        body_digest: [u8; 32],
+        op: F,
+    ) -> Result<T, (StatusCode, String)>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, (StatusCode, String)>>,
+    {
+        self.purge_expired();
+
+        // Clone the slot out so the DashMap shard isn't held across awaits.
+        let slot = self.slots.entry(key).or_default().clone();
+        let mut guard = slot.lock().await;
+
+        if let Some((at, digest, ref value)) = *guard {
+            if at.elapsed() < self.ttl {
+                if digest != body_digest {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Idempotency-Key was already used with a different request body".into(),
+                    ));
+                }
+                return Ok(value.clone());
+            }
+        }
+
+        let value = op().await?;
+        *guard = Some((Instant::now(), body_digest, value.clone()));
+        Ok(value)
+    }
+
+    /// Drop expired and empty slots that nobody is currently using.
+    fn purge_expired(&self) {
+        self.slots.retain(|_, slot| match slot.try_lock() {
+            Ok(guard) => guard
+                .as_ref()
+                .is_some_and(|(at, ..)| at.elapsed() < self.ttl),
+            Err(_) => true, // in use
+        });
+    }
+}
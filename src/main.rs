@@ -1,5 +1,14 @@
+pub mod access_log;
+pub mod audit_log;
+pub mod config;
+pub mod container_cache;
+pub mod docker_connect;
+pub mod docker_probe;
+pub mod idempotency;
 pub mod metric_poller;
 pub mod metric_registry;
+pub mod redact;
+pub mod retry;
 pub mod router;
 pub mod routes;
 pub mod spawn_docker_events_fanout;
@@ -8,111 +17,353 @@ pub mod stats;
 
 use std::collections::HashMap;
 use std::env;
+use std::net::SocketAddr;
+use std::os::unix::fs::FileTypeExt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
-use bollard::Docker;
-use bollard::API_DEFAULT_VERSION;
-use tokio::net::TcpListener;
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::net::{TcpListener, UnixListener};
 use tokio::signal;
+use tokio::signal::unix::SignalKind;
 use tokio::sync::broadcast;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use tokio::task::JoinHandle;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
 
+use crate::audit_log::AuditLog;
+use crate::config::Config;
+use crate::container_cache::ContainerListCache;
+use crate::docker_probe::spawn_docker_probe;
+use crate::idempotency::IdempotencyCache;
 use crate::metric_poller::poll_metrics_into_registry;
 use crate::metric_registry::MetricRegistry;
 use crate::router::build_router;
+use crate::routes::docker_passthrough::PASSTHROUGH_METHODS;
 use crate::spawn_docker_events_fanout::spawn_event_fanout;
 use crate::state::AppState;
 use crate::state::CpuSnapshot;
+use crate::state::IoSnapshot;
 use crate::stats::push_stats_to_ws_clients;
 
+/// How long each background task gets to wind down at shutdown.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// `ORQOS_TLS_CERT` / `ORQOS_TLS_KEY` (PEM paths) when both are set. Setting
+/// only one is a config mistake worth refusing to start over, rather than
+/// silently serving plaintext.
+fn tls_files() -> Option<(String, String)> {
+    let get = |key| env::var(key).ok().filter(|v| !v.is_empty());
+    match (get("ORQOS_TLS_CERT"), get("ORQOS_TLS_KEY")) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        (None, None) => None,
+        _ => {
+            error!("ORQOS_TLS_CERT and ORQOS_TLS_KEY must be set together");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Re-read the certificate and key on SIGHUP, so rotated certs are picked up
+/// without a restart. A bad pair is logged and the current one kept.
+fn spawn_tls_reload(tls: RustlsConfig, cert: String, key: String) {
+    let mut sighup = match signal::unix::signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!(?e, "failed to install SIGHUP handler; TLS reload disabled");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while sighup.recv().await.is_some() {
+            match tls.reload_from_pem_file(&cert, &key).await {
+                Ok(()) => info!("reloaded TLS certificate from {cert}"),
+                Err(e) => error!("TLS reload failed, keeping the current certificate: {e}"),
+            }
+        }
+    });
+}
+
+/// Wait up to [`SHUTDOWN_GRACE`] for `handle`, aborting it if it overruns.
+async fn join_or_abort(name: &str, mut handle: JoinHandle<()>) {
+    match tokio::time::timeout(SHUTDOWN_GRACE, &mut handle).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) if e.is_cancelled() => {}
+        Ok(Err(e)) => warn!(?e, "{name} task failed while shutting down"),
+        Err(_) => {
+            warn!("{name} task did not stop within {SHUTDOWN_GRACE:?}; abandoning it");
+            handle.abort();
+        }
+    }
+}
+
+/// Text logs by default, JSON lines with `ORQOS_LOG_FORMAT=json`. The level
+/// filter comes from `ORQOS_LOG`, then `RUST_LOG`, then `info`.
+fn init_tracing() {
+    let filter = EnvFilter::try_from_env("ORQOS_LOG")
+        .or_else(|_| EnvFilter::try_from_default_env())
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    let json =
+        std::env::var("ORQOS_LOG_FORMAT").is_ok_and(|f| f.trim().eq_ignore_ascii_case("json"));
+    if json {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    init_tracing();
 
-    let docker = match Docker::connect_with_local_defaults() {
-        Ok(d) => d,
-        Err(_) => {
-            // Fall back to Desktop socket if DEFAULT_SOCKET or DOCKER_HOST unset
-            let sock = format!("{}/.docker/desktop/docker.sock", std::env::var("HOME")?);
-            Docker::connect_with_unix(&sock, 120, API_DEFAULT_VERSION)?
+    let docker = docker_connect::connect()?;
+    // Don't crash-loop if dockerd isn't up yet: serve anyway (with /readyz
+    // at 503) and keep retrying in the background.
+    let docker_ready = match docker.version().await {
+        Ok(v) => {
+            tracing::info!(
+                "Connected to Docker {:?} (API {:?}), speaking API {}",
+                v.version,
+                v.api_version,
+                docker.client_version()
+            );
+            true
+        }
+        Err(e) => {
+            warn!(
+                ?e,
+                "Docker daemon not reachable yet; starting anyway (API {})",
+                docker.client_version()
+            );
+            false
         }
     };
-    tracing::info!("Connected to Docker {:?}", docker.version().await?.version);
 
-    // Events broadcast channel (100-message ring buffer)
-    let (events_tx, _) = broadcast::channel(100);
+    let config = Config::from_env();
+    info!("Stop timeout cap: {}s", config.max_stop_timeout);
+    if config.default_bind_ip.is_unspecified() {
+        warn!("Published ports bind to all interfaces by default");
+    } else {
+        info!(
+            "Published ports bind to {} by default",
+            config.default_bind_ip
+        );
+    }
+    if !config.metrics_enabled {
+        info!("Metric polling disabled (ORQOS_METRICS=off)");
+    }
+    for method in &config.docker_passthrough {
+        if !PASSTHROUGH_METHODS.contains(&method.as_str()) {
+            warn!("ORQOS_DOCKER_PASSTHROUGH: unknown method {method:?} ignored");
+        }
+    }
+    if !config.docker_passthrough.is_empty() {
+        warn!(
+            "Docker passthrough enabled for: {}",
+            config.docker_passthrough.join(", ")
+        );
+    }
+    if config.swagger_enabled {
+        info!("Swagger UI at {}", config.swagger_path);
+    } else {
+        info!("Swagger UI and OpenAPI spec disabled");
+    }
+
+    // Refuse to start rather than run execs that can't be audited
+    let audit = AuditLog::from_env().map_err(|e| anyhow::anyhow!("ORQOS_AUDIT_LOG: {e}"))?;
+    if !audit.is_enabled() {
+        warn!("Exec audit log disabled (ORQOS_AUDIT_LOG=off)");
+    }
+
+    // Events broadcast channel (ORQOS_EVENT_BUFFER-message ring buffer)
+    let (events_tx, _) = broadcast::channel(config.event_buffer);
 
     // Stats broadcast channel (100-message ring buffer)
     let (stats_tx, _) = broadcast::channel(100);
 
+    let container_cache = Arc::new(ContainerListCache::new(config.container_cache_ttl));
+
     // Spawn fan-out
-    let event_handle: JoinHandle<()> = spawn_event_fanout(docker.clone(), events_tx.clone());
+    let event_handle: JoinHandle<()> = spawn_event_fanout(
+        docker.clone(),
+        events_tx.clone(),
+        container_cache.clone(),
+        config.sensitive_keys.clone(),
+    );
 
     let metric_registry = MetricRegistry::default();
+    let idempotency_ttl = config.idempotency_ttl;
 
     let app_state = Arc::new(AppState {
+        config,
         docker,
         events_tx,
         stats_tx,
         metric_registry,
         cpu_snapshots: RwLock::<HashMap<String, CpuSnapshot>>::default(),
+        io_snapshots: RwLock::<HashMap<String, IoSnapshot>>::default(),
+        events_dropped: AtomicU64::new(0),
+        container_cache,
+        docker_ready: AtomicBool::new(docker_ready),
+        docker_version: Default::default(),
+        last_poll_ok: AtomicBool::new(false),
+        last_poll_time: AtomicU64::new(0),
+        last_poll_duration_us: AtomicU64::new(0),
+        poll_errors: AtomicU64::new(0),
+        create_idempotency: IdempotencyCache::new(idempotency_ttl),
+        audit,
     });
 
-    let router = build_router(app_state.clone());
+    if !docker_ready {
+        spawn_docker_probe(app_state.clone());
+    }
 
-    // Serve HTTP
-    let bind_addr = env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1:3000".into());
-    let listener = TcpListener::bind(&bind_addr).await?;
-    tracing::info!("Listening on {}", bind_addr);
+    let router = build_router(app_state.clone());
 
+    // Ctrl+C for local dev, SIGTERM from Docker/k8s
     let shutdown_signal = async {
-        if let Err(e) = signal::ctrl_c().await {
-            warn!(?e, "failed to install Ctrl+C handler");
+        let ctrl_c = async {
+            if let Err(e) = signal::ctrl_c().await {
+                warn!(?e, "failed to install Ctrl+C handler");
+                std::future::pending::<()>().await;
+            }
+        };
+        let terminate = async {
+            match signal::unix::signal(SignalKind::terminate()) {
+                Ok(mut sigterm) => {
+                    sigterm.recv().await;
+                }
+                Err(e) => {
+                    warn!(?e, "failed to install SIGTERM handler");
+                    std::future::pending::<()>().await;
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = terminate => {}
         }
         info!("shutdown signal received - closing HTTP server");
     };
 
     let state_clone = app_state.clone();
+    let metric_stop = Arc::new(Notify::new());
+    let metric_stop_rx = metric_stop.clone();
 
-    // Spawn metric polling task
-    let metric_handle: JoinHandle<()> = tokio::spawn(async move {
-        let interval = Duration::from_secs(5);
-        loop {
-            if let Err(e) = tokio::time::timeout(
-                Duration::from_secs(30),
-                poll_metrics_into_registry(state_clone.clone()),
-            )
-            .await
-            {
-                warn!(?e, "Metric polling timed out or failed");
-            }
+    // Spawn metric polling task; it exits at the next await once notified
+    let metric_handle: Option<JoinHandle<()>> = app_state.config.metrics_enabled.then(|| {
+        tokio::spawn(async move {
+            let interval = Duration::from_secs(5);
+            let cycle = async {
+                loop {
+                    if !state_clone.docker_ready.load(Ordering::Acquire) {
+                        tokio::time::sleep(interval).await;
+                        continue;
+                    }
 
-            push_stats_to_ws_clients(state_clone.clone());
+                    if let Err(e) = tokio::time::timeout(
+                        Duration::from_secs(30),
+                        poll_metrics_into_registry(state_clone.clone()),
+                    )
+                    .await
+                    {
+                        warn!(?e, "Metric polling timed out or failed");
+                    }
 
-            tokio::time::sleep(interval).await;
-        }
+                    push_stats_to_ws_clients(state_clone.clone());
+
+                    tokio::time::sleep(interval).await;
+                }
+            };
+
+            tokio::select! {
+                _ = metric_stop_rx.notified() => {}
+                _ = cycle => {}
+            }
+        })
     });
 
-    axum::serve(listener, router)
-        .with_graceful_shutdown(shutdown_signal)
-        .await?;
+    // Serve HTTP, over a unix socket if ORQOS_UNIX_SOCKET is set (sidecars)
+    if let Ok(sock_path) = env::var("ORQOS_UNIX_SOCKET") {
+        if tls_files().is_some() {
+            warn!("ORQOS_TLS_CERT/ORQOS_TLS_KEY are ignored with ORQOS_UNIX_SOCKET; serving plaintext");
+        }
+        // A socket left behind by a previous run would make bind fail;
+        // anything that isn't a socket is left alone.
+        if std::fs::symlink_metadata(&sock_path).is_ok_and(|m| m.file_type().is_socket()) {
+            std::fs::remove_file(&sock_path)?;
+        }
+        let listener = UnixListener::bind(&sock_path)?;
+        info!("Listening on unix:{}", sock_path);
 
-    // Clean shutdown: stop event stream task
-    event_handle.abort();
-    if let Err(e) = event_handle.await {
-        warn!(?e, "event fan-out task aborted while shutting down");
+        axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown_signal)
+            .await?;
+
+        let _ = std::fs::remove_file(&sock_path);
+    } else {
+        let raw = env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1:3000".into());
+        // Accepts IPv4 (`0.0.0.0:3000`) and bracketed IPv6 (`[::]:3000`)
+        let bind_addr: SocketAddr = match raw.parse() {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!(
+                    "Invalid BIND_ADDR {:?}: {} (expected e.g. 127.0.0.1:3000 or [::1]:3000)",
+                    raw, e
+                );
+                std::process::exit(2);
+            }
+        };
+
+        match tls_files() {
+            Some((cert, key)) => {
+                // Only ring is compiled in; rustls can't pick a provider on
+                // its own when more than one could be present.
+                let _ = rustls::crypto::ring::default_provider().install_default();
+                let tls = RustlsConfig::from_pem_file(&cert, &key)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("loading {cert} / {key}: {e}"))?;
+                info!("Listening on {} (TLS; HTTP/2 via ALPN)", bind_addr);
+                spawn_tls_reload(tls.clone(), cert, key);
+
+                let handle = axum_server::Handle::new();
+                let on_signal = handle.clone();
+                tokio::spawn(async move {
+                    shutdown_signal.await;
+                    on_signal.graceful_shutdown(Some(SHUTDOWN_GRACE));
+                });
+                axum_server::bind_rustls(bind_addr, tls)
+                    .handle(handle)
+                    .serve(router.into_make_service())
+                    .await?;
+            }
+            None => {
+                let listener = TcpListener::bind(bind_addr).await?;
+                info!("Listening on {} (HTTP/1.1 and h2c)", bind_addr);
+
+                axum::serve(listener, router)
+                    .with_graceful_shutdown(shutdown_signal)
+                    .await?;
+            }
+        }
     }
 
-    // Stop metric polling task
-    metric_handle.abort();
-    if let Err(e) = metric_handle.await {
-        warn!(?e, "metric polling task aborted while shutting down");
+    // Clean shutdown: ask the metric task to stop, abort the event stream,
+    // and don't wait on either for longer than SHUTDOWN_GRACE.
+    metric_stop.notify_one();
+    if let Some(handle) = metric_handle {
+        join_or_abort("metric polling", handle).await;
     }
 
+    event_handle.abort();
+    join_or_abort("event fan-out", event_handle).await;
+
     info!("Orqos terminated cleanly");
     Ok(())
 }
@@ -1,34 +1,98 @@
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::{atomic::Ordering, Arc};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use bollard::query_parameters::{ListContainersOptions, StatsOptions};
+use bollard::{
+    models::ContainerSummaryStateEnum,
+    query_parameters::{ListContainersOptions, StatsOptions},
+};
 use futures_util::StreamExt;
 
-use crate::state::{AppState, CpuSnapshot};
+use crate::metric_registry::HostGauges;
+use crate::retry::with_retry;
+use crate::state::{AppState, CpuSnapshot, IoSnapshot};
 
 pub async fn poll_metrics_into_registry(app_state: Arc<AppState>) {
-    if let Ok(containers) = app_state
-        .docker
-        .list_containers(Some(ListContainersOptions {
-            all: false,
-            limit: None,
-            size: false,
-            filters: None,
-        }))
-        .await
-    {
+    let started = Instant::now();
+    let retries = app_state.config.docker_retries;
+    let list_opts = ListContainersOptions {
+        all: false,
+        limit: None,
+        size: false,
+        filters: None,
+    };
+
+    let listed = with_retry("list_containers", retries, || {
+        app_state.docker.list_containers(Some(list_opts.clone()))
+    })
+    .await;
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    app_state.last_poll_time.store(now_ms, Ordering::Relaxed);
+    app_state
+        .last_poll_ok
+        .store(listed.is_ok(), Ordering::Relaxed);
+    if let Err(e) = &listed {
+        app_state.poll_errors.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            "Metrics poll skipped, series are going stale: listing containers failed: {e}"
+        );
+    }
+
+    if let Ok(containers) = listed {
+        // `info` carries the daemon-wide totals; running comes from the list
+        // we already have so it matches the per-container series.
+        match with_retry("info", retries, || app_state.docker.info()).await {
+            Ok(info) => app_state.metric_registry.record_host(HostGauges {
+                containers_running: containers.len() as u64,
+                containers_total: info.containers.unwrap_or(0).max(0) as u64,
+                images_total: info.images.unwrap_or(0).max(0) as u64,
+            }),
+            Err(e) => {
+                app_state.poll_errors.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!("Failed to fetch daemon info: {}", e)
+            }
+        }
+
         for c in containers {
             let id = c.id.unwrap_or_default();
 
-            let mut stats_stream = app_state.docker.stats(
-                &id,
-                Some(StatsOptions {
+            let name = c
+                .names
+                .as_ref()
+                .and_then(|n| n.first())
+                .map(|n| n.trim_start_matches('/').to_owned());
+            let labels: BTreeMap<String, String> = c
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(k, _)| app_state.config.metric_labels.contains(k))
+                .collect();
+            app_state.metric_registry.record_meta(&id, name, labels);
+            // A frozen cgroup accrues no CPU time, but the stats endpoint
+            // may keep reporting stale counters; pin CPU at zero instead.
+            let paused = c.state == Some(ContainerSummaryStateEnum::PAUSED);
+            app_state.metric_registry.record_paused(&id, paused);
+
+            let stats = with_retry("stats", retries, || async {
+                let opts = StatsOptions {
                     stream: false,
                     one_shot: true,
-                }),
-            );
+                };
+                app_state
+                    .docker
+                    .stats(&id, Some(opts))
+                    .next()
+                    .await
+                    .transpose()
+            })
+            .await;
 
-            match stats_stream.next().await {
-                Some(Ok(s)) => {
+            match stats {
+                Ok(Some(s)) => {
                     let cpu_stats = s.cpu_stats.as_ref();
                     let usage = cpu_stats.and_then(|cs| cs.cpu_usage.as_ref());
 
@@ -40,7 +104,9 @@ pub async fn poll_metrics_into_registry(app_state: Arc<AppState>) {
 
                     let prev = snapshots.get(&id);
 
-                    if let Some(prev) = prev {
+                    if paused {
+                        app_state.metric_registry.record_cpu(&id, 0.0);
+                    } else if let Some(prev) = prev {
                         let cpu_delta = total.saturating_sub(prev.total_usage);
                         let sys_delta = sys.saturating_sub(prev.system_usage);
 
@@ -66,16 +132,76 @@ pub async fn poll_metrics_into_registry(app_state: Arc<AppState>) {
                     let mem = s.memory_stats.as_ref().and_then(|m| m.usage).unwrap_or(0);
 
                     app_state.metric_registry.record_mem(&id, mem);
+
+                    let (net_rx, net_tx) = s
+                        .networks
+                        .as_ref()
+                        .map(|nets| {
+                            nets.values().fold((0, 0), |(rx, tx), n| {
+                                (rx + n.rx_bytes.unwrap_or(0), tx + n.tx_bytes.unwrap_or(0))
+                            })
+                        })
+                        .unwrap_or((0, 0));
+
+                    let (blk_read, blk_write) = s
+                        .blkio_stats
+                        .as_ref()
+                        .and_then(|b| b.io_service_bytes_recursive.as_ref())
+                        .map(|entries| {
+                            entries.iter().fold((0, 0), |(r, w), e| {
+                                let v = e.value.unwrap_or(0);
+                                match e.op.as_deref().map(str::to_ascii_lowercase).as_deref() {
+                                    Some("read") => (r + v, w),
+                                    Some("write") => (r, w + v),
+                                    _ => (r, w),
+                                }
+                            })
+                        })
+                        .unwrap_or((0, 0));
+
+                    let now = Instant::now();
+                    let mut io = app_state.io_snapshots.write().await;
+                    if let Some(prev) = io.get(&id) {
+                        let secs = now.duration_since(prev.at).as_secs_f64();
+                        if secs > 0.0 {
+                            let rate = |cur: u64, old: u64| cur.saturating_sub(old) as f64 / secs;
+                            app_state.metric_registry.record_net(
+                                &id,
+                                rate(net_rx, prev.net_rx),
+                                rate(net_tx, prev.net_tx),
+                            );
+                            app_state.metric_registry.record_blk(
+                                &id,
+                                rate(blk_read, prev.blk_read),
+                                rate(blk_write, prev.blk_write),
+                            );
+                        }
+                    }
+                    io.insert(
+                        id.clone(),
+                        IoSnapshot {
+                            at: now,
+                            net_rx,
+                            net_tx,
+                            blk_read,
+                            blk_write,
+                        },
+                    );
                 }
 
-                Some(Err(e)) => {
+                Err(e) => {
+                    app_state.poll_errors.fetch_add(1, Ordering::Relaxed);
                     tracing::debug!("Failed to fetch stats for container {}: {}", id, e);
                 }
 
-                None => {
+                Ok(None) => {
                     tracing::debug!("No stats returned for container {}", id);
                 }
             }
         }
     }
+
+    app_state
+        .last_poll_duration_us
+        .store(started.elapsed().as_micros() as u64, Ordering::Relaxed);
 }
@@ -1,21 +1,55 @@
 use std::{
-    collections::VecDeque,
-    sync::Arc,
-    time::{Duration, Instant},
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use dashmap::DashMap;
 
 /// Keep at most this many seconds of samples in each deque.
-const MAX_WINDOW: Duration = Duration::from_secs(60);
+pub const MAX_WINDOW: Duration = Duration::from_secs(60);
+
+/// One recorded value. `at` drives windowing/eviction (monotonic);
+/// `wall_ms` is what gets reported externally.
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    pub at: Instant,
+    /// Milliseconds since the UNIX epoch when the sample was taken
+    pub wall_ms: u64,
+    pub value: f64,
+}
+
+/// Daemon-wide counts, refreshed every poll cycle.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HostGauges {
+    pub containers_running: u64,
+    pub containers_total: u64,
+    pub images_total: u64,
+}
 
 /// Rolling-window metric registry (thread-safe, lock-free reads).
 #[derive(Clone, Default)]
 pub struct MetricRegistry {
-    /// container-id → deque of (timestamp, CPU fraction 0.0–1.0)
-    pub cpu: Arc<DashMap<String, VecDeque<(Instant, f64)>>>,
-    /// container-id → deque of (timestamp, memory bytes as f64)
-    pub mem: Arc<DashMap<String, VecDeque<(Instant, f64)>>>,
+    /// container-id → samples of (CPU fraction 0.0–1.0)
+    pub cpu: Arc<DashMap<String, VecDeque<Sample>>>,
+    /// container-id → samples of (memory bytes as f64)
+    pub mem: Arc<DashMap<String, VecDeque<Sample>>>,
+    /// container-id → samples of (network receive bytes/s)
+    pub net_rx: Arc<DashMap<String, VecDeque<Sample>>>,
+    /// container-id → samples of (network transmit bytes/s)
+    pub net_tx: Arc<DashMap<String, VecDeque<Sample>>>,
+    /// container-id → samples of (block-device read bytes/s)
+    pub blk_read: Arc<DashMap<String, VecDeque<Sample>>>,
+    /// container-id → samples of (block-device write bytes/s)
+    pub blk_write: Arc<DashMap<String, VecDeque<Sample>>>,
+    /// container-id → container name (leading `/` stripped)
+    pub names: Arc<DashMap<String, String>>,
+    /// container-id → allowlisted Docker labels, used as metric dimensions
+    pub labels: Arc<DashMap<String, BTreeMap<String, String>>>,
+    /// container-id → whether it was paused at the last poll
+    pub paused: Arc<DashMap<String, bool>>,
+    /// Latest host-level gauges (`None` until the first successful poll)
+    pub host: Arc<RwLock<Option<HostGauges>>>,
 }
 
 impl MetricRegistry {
@@ -31,6 +65,61 @@ impl MetricRegistry {
         Self::insert_sample(guard.value_mut(), bytes as f64);
     }
 
+    pub fn record_net(&self, id: &str, rx_rate: f64, tx_rate: f64) {
+        Self::insert_sample(
+            self.net_rx.entry(id.to_owned()).or_default().value_mut(),
+            rx_rate,
+        );
+        Self::insert_sample(
+            self.net_tx.entry(id.to_owned()).or_default().value_mut(),
+            tx_rate,
+        );
+    }
+
+    pub fn record_blk(&self, id: &str, read_rate: f64, write_rate: f64) {
+        Self::insert_sample(
+            self.blk_read.entry(id.to_owned()).or_default().value_mut(),
+            read_rate,
+        );
+        Self::insert_sample(
+            self.blk_write.entry(id.to_owned()).or_default().value_mut(),
+            write_rate,
+        );
+    }
+
+    pub fn record_meta(&self, id: &str, name: Option<String>, labels: BTreeMap<String, String>) {
+        match name {
+            Some(name) => self.names.insert(id.to_owned(), name),
+            None => self.names.remove(id).map(|(_, v)| v),
+        };
+        self.labels.insert(id.to_owned(), labels);
+    }
+
+    pub fn record_paused(&self, id: &str, paused: bool) {
+        self.paused.insert(id.to_owned(), paused);
+    }
+
+    pub fn is_paused(&self, id: &str) -> Option<bool> {
+        self.paused.get(id).map(|p| *p)
+    }
+
+    pub fn record_host(&self, gauges: HostGauges) {
+        *self.host.write().unwrap_or_else(|e| e.into_inner()) = Some(gauges);
+    }
+
+    pub fn host_gauges(&self) -> Option<HostGauges> {
+        *self.host.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Human-friendly name for `id`: the container name when known, else the
+    /// 12-char short ID Docker itself displays.
+    pub fn display_name(&self, id: &str) -> String {
+        self.names
+            .get(id)
+            .map(|n| n.clone())
+            .unwrap_or_else(|| id.chars().take(12).collect())
+    }
+
     pub fn cpu_avg(&self, id: &str, window: Duration) -> Option<f64> {
         self.avg(&self.cpu, id, window)
     }
@@ -39,13 +128,63 @@ impl MetricRegistry {
         self.max(&self.mem, id, window).map(|v| v as u64)
     }
 
+    pub fn net_rx_rate(&self, id: &str, window: Duration) -> Option<f64> {
+        self.avg(&self.net_rx, id, window)
+    }
+
+    pub fn net_tx_rate(&self, id: &str, window: Duration) -> Option<f64> {
+        self.avg(&self.net_tx, id, window)
+    }
+
+    pub fn blk_read_rate(&self, id: &str, window: Duration) -> Option<f64> {
+        self.avg(&self.blk_read, id, window)
+    }
+
+    pub fn blk_write_rate(&self, id: &str, window: Duration) -> Option<f64> {
+        self.avg(&self.blk_write, id, window)
+    }
+
+    /// Raw CPU samples within `window` as `(epoch_ms, value)`, oldest first.
+    pub fn cpu_series(&self, id: &str, window: Duration) -> Option<Vec<(u64, f64)>> {
+        self.series(&self.cpu, id, window)
+    }
+
+    /// Raw memory samples (bytes) within `window`, oldest first.
+    pub fn mem_series(&self, id: &str, window: Duration) -> Option<Vec<(u64, f64)>> {
+        self.series(&self.mem, id, window)
+    }
+
+    /// Map a user-supplied container reference (full ID, ID prefix or name)
+    /// onto the full ID the registry is keyed by.
+    pub fn resolve_id(&self, id_or_name: &str) -> Option<String> {
+        let name = id_or_name.trim_start_matches('/');
+        if self.cpu.contains_key(id_or_name) {
+            return Some(id_or_name.to_owned());
+        }
+        if let Some(e) = self.names.iter().find(|e| e.value() == name) {
+            return Some(e.key().clone());
+        }
+        self.cpu
+            .iter()
+            .find(|e| e.key().starts_with(id_or_name))
+            .map(|e| e.key().clone())
+    }
+
     /* ──────────── internals ──────────── */
 
-    fn insert_sample(q: &mut VecDeque<(Instant, f64)>, value: f64) {
+    fn insert_sample(q: &mut VecDeque<Sample>, value: f64) {
         let now = Instant::now();
-        q.push_back((now, value));
-        while let Some((ts, _)) = q.front() {
-            if now.duration_since(*ts) > MAX_WINDOW {
+        let wall_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        q.push_back(Sample {
+            at: now,
+            wall_ms,
+            value,
+        });
+        while let Some(front) = q.front() {
+            if now.duration_since(front.at) > MAX_WINDOW {
                 q.pop_front();
             } else {
                 break;
@@ -53,9 +192,24 @@ impl MetricRegistry {
         }
     }
 
+    fn series(
+        &self,
+        map: &DashMap<String, VecDeque<Sample>>,
+        id: &str,
+        window: Duration,
+    ) -> Option<Vec<(u64, f64)>> {
+        map.get(id).map(|q| {
+            let now = Instant::now();
+            q.iter()
+                .filter(|s| now.duration_since(s.at) <= window)
+                .map(|s| (s.wall_ms, s.value))
+                .collect()
+        })
+    }
+
     fn avg(
         &self,
-        map: &DashMap<String, VecDeque<(Instant, f64)>>,
+        map: &DashMap<String, VecDeque<Sample>>,
         id: &str,
         window: Duration,
     ) -> Option<f64> {
@@ -63,11 +217,11 @@ impl MetricRegistry {
             let now = Instant::now();
             let mut sum = 0.0;
             let mut n = 0;
-            for &(ts, v) in q.iter().rev() {
-                if now.duration_since(ts) > window {
+            for s in q.iter().rev() {
+                if now.duration_since(s.at) > window {
                     break;
                 }
-                sum += v;
+                sum += s.value;
                 n += 1;
             }
             (n > 0).then(|| sum / n as f64)
@@ -76,7 +230,7 @@ impl MetricRegistry {
 
     fn max(
         &self,
-        map: &DashMap<String, VecDeque<(Instant, f64)>>,
+        map: &DashMap<String, VecDeque<Sample>>,
         id: &str,
         window: Duration,
     ) -> Option<f64> {
@@ -84,8 +238,8 @@ impl MetricRegistry {
             let now = Instant::now();
             q.iter()
                 .rev()
-                .take_while(|&&(ts, _)| now.duration_since(ts) <= window)
-                .map(|&(_, v)| v)
+                .take_while(|s| now.duration_since(s.at) <= window)
+                .map(|s| s.value)
                 .filter(|v| !v.is_nan())
                 .max_by(|a, b| a.partial_cmp(b).unwrap())
         })
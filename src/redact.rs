@@ -0,0 +1,121 @@
+//! Masking of secret-looking env and label values in API responses.
+
+use std::collections::HashMap;
+
+use regex::{Regex, RegexBuilder};
+
+/// Stand-in for masked values.
+pub(crate) const REDACTED: &str = "***";
+
+/// Compile key globs (`*` wildcard, matched case-insensitively against the
+/// whole key) into one regex. `None` if there are none.
+pub(crate) fn sensitive_key_regex(globs: &[String]) -> Option<Regex> {
+    if globs.is_empty() {
+        return None;
+    }
+    let alternatives: Vec<String> = globs
+        .iter()
+        .map(|g| {
+            g.split('*')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".*")
+        })
+        .collect();
+    RegexBuilder::new(&format!("^(?:{})$", alternatives.join("|")))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+/// Mask the value of a `KEY=VALUE` entry if its key is sensitive.
+pub(crate) fn redact_env_entry(sensitive: Option<&Regex>, entry: String) -> String {
+    match (sensitive, entry.split_once('=')) {
+        (Some(re), Some((key, _))) if re.is_match(key) => format!("{key}={REDACTED}"),
+        _ => entry,
+    }
+}
+
+/// Mask, in place, the values of sensitive label keys.
+pub(crate) fn redact_labels(sensitive: Option<&Regex>, labels: &mut HashMap<String, String>) {
+    let Some(re) = sensitive else { return };
+    for (key, value) in labels.iter_mut() {
+        if re.is_match(key) {
+            *value = REDACTED.to_owned();
+        }
+    }
+}
+
+/// Mask an inspect's `Config.Env` and `Config.Labels` (container or image
+/// config; both use the same two fields).
+pub(crate) fn redact_config(
+    sensitive: Option<&Regex>,
+    env: &mut Option<Vec<String>>,
+    labels: &mut Option<HashMap<String, String>>,
+) {
+    if sensitive.is_none() {
+        return;
+    }
+    if let Some(env) = env {
+        for entry in env.iter_mut() {
+            *entry = redact_env_entry(sensitive, std::mem::take(entry));
+        }
+    }
+    if let Some(labels) = labels {
+        redact_labels(sensitive, labels);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn re(globs: &[&str]) -> Regex {
+        let globs: Vec<String> = globs.iter().map(|g| g.to_string()).collect();
+        sensitive_key_regex(&globs).unwrap()
+    }
+
+    #[test]
+    fn globs_match_whole_keys() {
+        let re = re(&["*_TOKEN", "DB_*_URL", "SECRET"]);
+        assert!(re.is_match("GITHUB_TOKEN"));
+        assert!(!re.is_match("TOKENS"));
+        assert!(re.is_match("DB_MAIN_URL"));
+        assert!(re.is_match("SECRET"));
+        assert!(!re.is_match("SECRETS"));
+    }
+
+    #[test]
+    fn globs_are_case_insensitive_and_literal() {
+        let re = re(&["*_password", "a.b"]);
+        assert!(re.is_match("db_Password"));
+        assert!(!re.is_match("PASSWORD_HINT"));
+        assert!(!re.is_match("aXb"));
+    }
+
+    #[test]
+    fn env_entries_keep_their_key() {
+        let re = re(&["*_KEY"]);
+        assert_eq!(
+            redact_env_entry(Some(&re), "API_KEY=abc=def".into()),
+            "API_KEY=***"
+        );
+        assert_eq!(redact_env_entry(Some(&re), "PATH=/bin".into()), "PATH=/bin");
+        assert_eq!(redact_env_entry(None, "API_KEY=abc".into()), "API_KEY=abc");
+    }
+
+    #[test]
+    fn config_env_and_labels_are_masked() {
+        let re = re(&["*_TOKEN"]);
+        let mut env = Some(vec!["GH_TOKEN=abc".to_string(), "HOME=/root".to_string()]);
+        let mut labels = Some(HashMap::from([("ci_token".to_string(), "x".to_string())]));
+        redact_config(Some(&re), &mut env, &mut labels);
+        assert_eq!(env.unwrap(), ["GH_TOKEN=***", "HOME=/root"]);
+        assert_eq!(labels.unwrap()["ci_token"], REDACTED);
+    }
+
+    #[test]
+    fn no_globs_means_no_regex() {
+        assert!(sensitive_key_regex(&[]).is_none());
+    }
+}
@@ -0,0 +1,51 @@
+use std::{future::Future, time::Duration};
+
+use bollard::errors::Error as BollardError;
+use rand::Rng;
+use tokio::time::sleep;
+
+/// Whether `err` looks like a transient daemon/transport hiccup (a dropped
+/// connection, a 5xx such as `500 EOF`) rather than a real answer.
+fn is_transient(err: &BollardError) -> bool {
+    match err {
+        BollardError::DockerResponseServerError { status_code, .. } => *status_code >= 500,
+        BollardError::IOError { .. }
+        | BollardError::HyperResponseError { .. }
+        | BollardError::HyperLegacyError { .. }
+        | BollardError::RequestTimeoutError => true,
+        _ => false,
+    }
+}
+
+/// Longest sleep between two attempts, before jitter.
+const MAX_BACKOFF_MS: u64 = 5_000;
+
+/// Run an *idempotent* Docker call up to `attempts` times, sleeping with
+/// jittered exponential backoff (100ms, 200ms, … up to 5s, plus up to 100ms)
+/// between transient failures. Never use this for calls with side effects.
+pub(crate) async fn with_retry<T, F, Fut>(
+    op: &str,
+    attempts: u32,
+    mut call: F,
+) -> Result<T, BollardError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, BollardError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(err) if attempt < attempts && is_transient(&err) => {
+                let base = 2u64
+                    .saturating_pow(attempt - 1)
+                    .saturating_mul(100)
+                    .min(MAX_BACKOFF_MS);
+                let backoff = Duration::from_millis(base + rand::rng().random_range(0..100));
+                tracing::debug!(op, attempt, ?backoff, %err, "transient Docker error, retrying");
+                sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
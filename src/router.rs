@@ -1,19 +1,46 @@
 use std::sync::Arc;
 
-use axum::routing::post;
+use axum::extract::DefaultBodyLimit;
+use axum::middleware;
+use axum::routing::{delete, post, put};
 use axum::{routing::get, Router};
+use tower_http::compression::CompressionLayer;
 use utoipa::OpenApi;
 
+use crate::access_log::access_log;
+use crate::audit_log::audit_mutations;
+use crate::routes::attach::attach_ws_handler;
+use crate::routes::container_commit::commit_container_handler;
 use crate::routes::container_create::create_container_handler;
+use crate::routes::container_create_stream::create_container_stream_handler;
+use crate::routes::container_env::container_env_handler;
+use crate::routes::container_export::export_container_handler;
+use crate::routes::container_health::health_ws_handler;
+use crate::routes::container_history::container_history_handler;
+use crate::routes::container_recreate::recreate_container_handler;
 use crate::routes::container_remove::remove_container_handler;
+use crate::routes::container_restart_policy::update_restart_policy_handler;
+use crate::routes::container_stats::container_stats_handler;
 use crate::routes::container_stop::stop_container_handler;
+use crate::routes::containers_bulk::{bulk_remove_handler, bulk_stop_handler};
 pub use crate::routes::containers_list::list_containers_handler;
+use crate::routes::delete_file::delete_file_handler;
+use crate::routes::docker_passthrough::docker_passthrough_handler;
 use crate::routes::events_ws::events_ws;
-use crate::routes::exec::{exec_once_handler, exec_ws_handler};
-use crate::routes::metrics::metrics_handler;
+use crate::routes::exec::{exec_inspect_handler, exec_once_handler, exec_ws_handler};
+use crate::routes::images::{push_image_handler, tag_image_handler};
+use crate::routes::jobs::run_job_handler;
+use crate::routes::list_dir::list_dir_handler;
+use crate::routes::logs::{
+    logs_download_handler, logs_handler, logs_ws_handler, multi_logs_ws_handler,
+};
+use crate::routes::metrics::{metrics_handler, metrics_json_handler};
 use crate::routes::read_file::read_file_handler;
-use crate::routes::stats_ws::stats_ws;
-use crate::routes::write_file::write_file_handler;
+use crate::routes::readyz::readyz_handler;
+use crate::routes::stacks::{create_stack_handler, delete_stack_handler};
+use crate::routes::stats_ws::{metrics_ws, stats_ws};
+use crate::routes::version::version_handler;
+use crate::routes::write_file::{write_file_handler, write_files_handler};
 use crate::state::AppState;
 
 #[derive(OpenApi)]
@@ -22,33 +49,131 @@ use crate::state::AppState;
     paths(
         crate::routes::containers_list::list_containers_handler,
         crate::routes::container_stop::stop_container_handler,
+        crate::routes::containers_bulk::bulk_stop_handler,
+        crate::routes::containers_bulk::bulk_remove_handler,
         crate::routes::container_remove::remove_container_handler,
         crate::routes::container_create::create_container_handler,
+        crate::routes::container_create_stream::create_container_stream_handler,
+        crate::routes::container_stats::container_stats_handler,
+        crate::routes::container_history::container_history_handler,
+        crate::routes::container_env::container_env_handler,
+        crate::routes::container_export::export_container_handler,
+        crate::routes::container_commit::commit_container_handler,
+        crate::routes::container_recreate::recreate_container_handler,
+        crate::routes::container_restart_policy::update_restart_policy_handler,
         crate::routes::exec::exec_once_handler,
+        crate::routes::exec::exec_inspect_handler,
+        crate::routes::exec::exec_ws_handler,
+        crate::routes::attach::attach_ws_handler,
+        crate::routes::container_health::health_ws_handler,
+        crate::routes::jobs::run_job_handler,
+        crate::routes::stacks::create_stack_handler,
+        crate::routes::stacks::delete_stack_handler,
+        crate::routes::docker_passthrough::docker_passthrough_handler,
+        crate::routes::images::tag_image_handler,
+        crate::routes::images::push_image_handler,
         crate::routes::write_file::write_file_handler,
+        crate::routes::write_file::write_files_handler,
         crate::routes::read_file::read_file_handler,
+        crate::routes::delete_file::delete_file_handler,
+        crate::routes::list_dir::list_dir_handler,
+        crate::routes::logs::logs_handler,
+        crate::routes::logs::logs_ws_handler,
+        crate::routes::logs::logs_download_handler,
+        crate::routes::logs::multi_logs_ws_handler,
+        crate::routes::metrics::metrics_handler,
+        crate::routes::metrics::metrics_json_handler,
+        crate::routes::readyz::readyz_handler,
+        crate::routes::version::version_handler,
         crate::routes::events_ws::events_ws,
-        crate::routes::stats_ws::stats_ws
+        crate::routes::stats_ws::stats_ws,
+        crate::routes::stats_ws::metrics_ws
     )
 )]
 struct ApiDoc;
 
 pub(crate) fn build_router(app: Arc<AppState>) -> Router {
-    Router::new()
+    let swagger = app
+        .config
+        .swagger_enabled
+        .then(|| app.config.swagger_path.clone());
+
+    // Every body is capped (413 beyond): control endpoints by the small
+    // router-wide limit, file uploads by their own larger one, which wins
+    // because it sits closer to the handler.
+    let body_limit = DefaultBodyLimit::max(app.config.max_body_bytes);
+    let upload_limit = DefaultBodyLimit::max(app.config.max_upload_bytes);
+
+    let router = Router::new()
         .route("/containers", get(list_containers_handler))
         .route("/containers", post(create_container_handler))
+        .route("/containers/stop", post(bulk_stop_handler))
+        .route("/containers/remove", post(bulk_remove_handler))
         .route("/containers/{id}/stop", post(stop_container_handler))
         .route("/containers/{id}/remove", post(remove_container_handler))
+        .route("/containers/{id}/stats", get(container_stats_handler))
+        .route("/containers/{id}/history", get(container_history_handler))
+        .route("/containers/{id}/env", get(container_env_handler))
+        .route("/containers/{id}/commit", post(commit_container_handler))
+        .route(
+            "/containers/{id}/recreate",
+            post(recreate_container_handler),
+        )
+        .route(
+            "/containers/{id}/restart-policy",
+            put(update_restart_policy_handler),
+        )
         .route("/containers/{id}/exec", post(exec_once_handler))
+        .route("/containers/{id}/exec/{exec_id}", get(exec_inspect_handler))
+        .route(
+            "/containers/{id}/write-file",
+            post(write_file_handler).layer(upload_limit),
+        )
+        .route(
+            "/containers/{id}/write-files",
+            post(write_files_handler).layer(upload_limit),
+        )
+        .route("/containers/{id}/delete-file", post(delete_file_handler))
+        .route("/containers/{id}/list-dir", post(list_dir_handler))
+        .route("/containers/{id}/logs", get(logs_handler))
+        .route("/jobs", post(run_job_handler))
+        .route("/stacks", post(create_stack_handler))
+        .route("/stacks/{name}", delete(delete_stack_handler))
+        .route("/images/{id}/tag", post(tag_image_handler))
+        .route("/docker/{method}", post(docker_passthrough_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/metrics.json", get(metrics_json_handler))
+        .route("/readyz", get(readyz_handler))
+        .route("/version", get(version_handler))
+        // Only the routes above get compressed: WS upgrades, raw file bytes
+        // (often already compressed) and progress streams are registered
+        // below it.
+        .layer(CompressionLayer::new())
         .route("/containers/{id}/exec/ws", get(exec_ws_handler))
-        .route("/containers/{id}/write-file", post(write_file_handler))
+        .route("/containers/{id}/logs/ws", get(logs_ws_handler))
+        .route("/containers/{id}/logs/download", get(logs_download_handler))
+        .route("/containers/{id}/attach/ws", get(attach_ws_handler))
+        .route("/containers/{id}/health/ws", get(health_ws_handler))
         .route("/containers/{id}/read-file", post(read_file_handler))
-        .route("/metrics", get(metrics_handler))
+        .route("/containers/{id}/export", get(export_container_handler))
+        .route("/images/{id}/push", post(push_image_handler))
+        .route("/containers/stream", post(create_container_stream_handler))
+        .route("/logs/ws", get(multi_logs_ws_handler))
         .route("/events/ws", get(events_ws))
         .route("/stats/ws", get(stats_ws))
-        .with_state(app)
-        .merge(
-            utoipa_swagger_ui::SwaggerUi::new("/swagger")
-                .url("/api/openapi.json", ApiDoc::openapi()),
-        )
+        .route("/metrics/ws", get(metrics_ws))
+        .layer(middleware::from_fn_with_state(app.clone(), audit_mutations))
+        .layer(body_limit)
+        .with_state(app.clone());
+
+    // Both the UI and the spec stay unmounted when Swagger is switched off.
+    let router = match swagger {
+        Some(path) => router.merge(
+            utoipa_swagger_ui::SwaggerUi::new(path).url("/api/openapi.json", ApiDoc::openapi()),
+        ),
+        None => router,
+    };
+
+    // Outermost, so it sees every route (Swagger included) and final statuses.
+    router.layer(middleware::from_fn_with_state(app, access_log))
 }
@@ -0,0 +1,171 @@
+use std::{io, pin::Pin, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::IntoResponse,
+};
+use bollard::{
+    container::{AttachContainerResults, LogOutput},
+    query_parameters::AttachContainerOptionsBuilder,
+};
+use futures::{stream::SplitStream, SinkExt};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{routes::container_id::validate_container_id, state::AppState};
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AttachQuery {
+    /// Replay the container's prior output before streaming live
+    #[param(required = false)]
+    logs: Option<bool>,
+    /// Forward client frames to the process's stdin; without it the attach
+    /// is read-only and client input is discarded
+    #[param(required = false)]
+    stdin: Option<bool>,
+}
+
+/// Attach to the container's main process (PID 1) rather than spawning a new
+/// one like exec does. With `stdin=true`, client text/binary frames are
+/// written to its stdin, which is flushed and closed when the client leaves.
+///
+/// Stdin only reaches the process if the container was created with
+/// `OpenStdin`; with `StdinOnce` the process sees EOF once this socket goes.
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/attach/ws",
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        AttachQuery,
+    ),
+    responses(
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 400, description = "Invalid container ID"),
+    ),
+    tag = "Streaming",
+    summary = "Attach to a container's stdio over WebSocket",
+    description = "Streams `{\"stream\": \"stdout|stderr\", \"data\": ...}` text frames from the container's main process, and with `stdin=true` forwards incoming frames to its stdin, until the process exits or the client disconnects."
+)]
+pub async fn attach_ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<AttachQuery>,
+) -> impl IntoResponse {
+    let container = match validate_container_id(&container) {
+        Ok(id) => id.to_owned(),
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let (logs, stdin) = (q.logs.unwrap_or(false), q.stdin.unwrap_or(false));
+    ws.on_upgrade(move |socket| attach_over_ws(socket, state, container, logs, stdin))
+}
+
+async fn attach_over_ws(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    container: String,
+    logs: bool,
+    stdin: bool,
+) {
+    let opts = AttachContainerOptionsBuilder::new()
+        .stream(true)
+        .stdin(stdin)
+        .stdout(true)
+        .stderr(true)
+        .logs(logs)
+        .build();
+
+    let AttachContainerResults { mut output, input } =
+        match state.docker.attach_container(&container, Some(opts)).await {
+            Ok(a) => a,
+            Err(e) => {
+                let _ = socket
+                    .send(json!({ "error": e.to_string() }).to_string().into())
+                    .await;
+                let _ = socket.close().await;
+                return;
+            }
+        };
+
+    let (mut tx, rx) = socket.split();
+    // Reads client frames until it disconnects, so that is noticed even on
+    // a read-only attach.
+    let mut pump = tokio::spawn(pump_stdin(rx, stdin.then_some(input)));
+
+    loop {
+        tokio::select! {
+            frame = output.next() => {
+                let (stream, message) = match frame {
+                    Some(Ok(LogOutput::StdOut { message } | LogOutput::Console { message })) => {
+                        ("stdout", message)
+                    }
+                    Some(Ok(LogOutput::StdErr { message })) => ("stderr", message),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        let _ = tx
+                            .send(json!({ "error": e.to_string() }).to_string().into())
+                            .await;
+                        break;
+                    }
+                    None => break, // process exited
+                };
+                let payload = json!({ "stream": stream, "data": String::from_utf8_lossy(&message) });
+                if tx.send(Message::Text(payload.to_string().into())).await.is_err() {
+                    break;
+                }
+            }
+            pumped = &mut pump => {
+                // Either a write to stdin failed, which the client is told
+                // about, or the client went away and stdin is already closed
+                if let Ok(Err(e)) = pumped {
+                    let _ = tx
+                        .send(json!({ "error": format!("writing to stdin: {e}") }).to_string().into())
+                        .await;
+                }
+                break;
+            }
+        }
+    }
+
+    pump.abort();
+    let _ = tx.close().await;
+}
+
+/// Copy client frames to the process's stdin (`None` for read-only
+/// attaches, whose input is dropped). Flushes and closes stdin once the
+/// client disconnects, so the process sees EOF; fails if a write to stdin
+/// does.
+async fn pump_stdin(
+    mut rx: SplitStream<WebSocket>,
+    mut stdin: Option<Pin<Box<dyn AsyncWrite + Send>>>,
+) -> io::Result<()> {
+    while let Some(Ok(msg)) = rx.next().await {
+        let data = match msg {
+            Message::Text(t) => Bytes::from(t),
+            Message::Binary(b) => b,
+            Message::Close(_) => break,
+            _ => continue, // ping/pong
+        };
+        let Some(input) = stdin.as_mut() else {
+            continue;
+        };
+        // stdin is gone (process exited or closed it); nothing left to close
+        input.write_all(&data).await?;
+        input.flush().await?;
+    }
+
+    if let Some(mut input) = stdin {
+        let _ = input.flush().await;
+        let _ = input.shutdown().await;
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use bollard::{
+    errors::Error as BollardError, models::ContainerConfig,
+    query_parameters::CommitContainerOptionsBuilder,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    routes::{container_id::validate_container_id, exec::map_docker_err},
+    state::AppState,
+};
+
+lazy_static! {
+    // [registry[:port]/]path/components — lowercase, per the distribution spec
+    static ref REPO_RE: Regex = Regex::new(
+        r"^(?:[a-zA-Z0-9.-]+(?::[0-9]+)?/)?[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*(?:/[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*)*$"
+    )
+    .unwrap();
+    static ref TAG_RE: Regex = Regex::new(r"^[A-Za-z0-9_][A-Za-z0-9_.-]{0,127}$").unwrap();
+}
+
+/// Instructions `docker commit --change` accepts.
+const ALLOWED_CHANGES: &[&str] = &[
+    "CMD",
+    "ENTRYPOINT",
+    "ENV",
+    "EXPOSE",
+    "LABEL",
+    "ONBUILD",
+    "STOPSIGNAL",
+    "USER",
+    "VOLUME",
+    "WORKDIR",
+];
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CommitRequest {
+    /// Repository for the new image, e.g. `registry.local:5000/team/app`
+    pub repo: String,
+    /// Defaults to `latest`
+    pub tag: Option<String>,
+    /// Commit message
+    pub message: Option<String>,
+    /// Dockerfile instructions to apply, e.g. `["ENV DEBUG=1", "WORKDIR /app"]`
+    #[serde(default)]
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommitResponse {
+    /// ID of the new image (`sha256:…`)
+    pub id: String,
+}
+
+pub(crate) fn validate_repo(repo: &str) -> Result<(), (StatusCode, String)> {
+    if repo.len() > 255 || !REPO_RE.is_match(repo) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("invalid repository name: {repo:?}"),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_tag(tag: &str) -> Result<(), (StatusCode, String)> {
+    if !TAG_RE.is_match(tag) {
+        return Err((StatusCode::BAD_REQUEST, format!("invalid tag: {tag:?}")));
+    }
+    Ok(())
+}
+
+fn validate_change(change: &str) -> Result<(), (StatusCode, String)> {
+    let instruction = change.split_whitespace().next().unwrap_or("");
+    if change.contains('\n') || !ALLOWED_CHANGES.contains(&instruction.to_uppercase().as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("unsupported change: {change:?}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Snapshot a container's filesystem (and optional config changes) into a
+/// new image. The container is paused while committing.
+#[utoipa::path(
+    post,
+    path = "/containers/{id}/commit",
+    request_body = CommitRequest,
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    responses(
+        (status = 201, description = "Image created", body = CommitResponse),
+        (status = 400, description = "Invalid repo, tag or change"),
+        (status = 404, description = "Container not found"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+)]
+pub async fn commit_container_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Json(req): Json<CommitRequest>,
+) -> Result<(StatusCode, Json<CommitResponse>), (StatusCode, String)> {
+    let container = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_owned();
+    validate_repo(&req.repo)?;
+    let tag = req.tag.as_deref().unwrap_or("latest");
+    validate_tag(tag)?;
+    for change in &req.changes {
+        validate_change(change)?;
+    }
+
+    let mut opts = CommitContainerOptionsBuilder::new()
+        .container(&container)
+        .repo(&req.repo)
+        .tag(tag)
+        .pause(true);
+    if let Some(message) = &req.message {
+        opts = opts.comment(message);
+    }
+    if !req.changes.is_empty() {
+        opts = opts.changes(&req.changes.join("\n"));
+    }
+
+    let resp = state
+        .docker
+        .commit_container(opts.build(), ContainerConfig::default())
+        .await
+        .map_err(|e| match e {
+            // The daemon rejects malformed `changes` with a 400
+            BollardError::DockerResponseServerError {
+                status_code: 400,
+                message,
+            } => (StatusCode::BAD_REQUEST, message),
+            e => map_docker_err(e),
+        })?;
+
+    tracing::info!(container, image = %resp.id, "committed container");
+    Ok((StatusCode::CREATED, Json(CommitResponse { id: resp.id })))
+}
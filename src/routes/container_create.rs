@@ -1,16 +1,25 @@
 use axum::{
     extract::{Json, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
 };
 use bollard::{
-    models::{ContainerCreateBody, PortBinding},
-    query_parameters::CreateContainerOptions,
+    models::{
+        ContainerCreateBody, EndpointSettings, NetworkingConfig, PortBinding, RestartPolicy,
+        RestartPolicyNameEnum,
+    },
+    query_parameters::{CreateContainerOptions, InspectContainerOptions, LogsOptionsBuilder},
     service::HostConfig,
     Docker,
 };
-use rand::{rng, Rng};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
 use utoipa::ToSchema;
 
 use crate::state::AppState;
@@ -23,16 +32,56 @@ pub struct ContainerCreate {
     pub memory: Option<String>, // "1g"
     pub swap: Option<String>,   // "2g"
     pub env: Option<Vec<String>>,
-    pub ports: Option<Vec<PortMap>>, // [{container: 8080, host: 0}]
+    /// Host path of a `KEY=VALUE` file merged under `env` (which wins)
+    pub env_file: Option<String>,
+    pub ports: Option<Vec<PortMap>>, // [{container: 8080, host: 0, proto: "tcp"}]
     pub network: Option<String>,     // "rawpair-net" (defaults to "bridge")
     pub volumes: Option<Vec<VolumeMap>>, // [{source:"/host",target:"/data",ro:false}]
+    /// Merged with `com.orqos.managed=true` and `com.orqos.created=<RFC 3339>`
     pub labels: Option<HashMap<String, String>>,
+    /// `no` (default), `always`, `unless-stopped` or `on-failure[:N]`
+    pub restart_policy: Option<String>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct PortMap {
     pub container: u16,
+    /// Omit (or `0`) to let Docker pick a free port on its host
     pub host: Option<u16>,
+    /// `tcp` (default) or `udp`
+    pub proto: Option<String>,
+    /// Host interface to publish on; defaults to `127.0.0.1` (see
+    /// `ORQOS_PUBLIC_PORTS` and `ORQOS_DEFAULT_BIND_IP`)
+    pub host_ip: Option<String>,
+}
+
+impl PortMap {
+    fn proto(&self) -> Result<&str, (StatusCode, String)> {
+        match self.proto.as_deref().unwrap_or("tcp") {
+            p @ ("tcp" | "udp") => Ok(p),
+            other => Err((
+                StatusCode::BAD_REQUEST,
+                format!("ports: invalid proto {other:?}, expected tcp or udp"),
+            )),
+        }
+    }
+
+    /// Docker's `port/proto` key, e.g. `53/udp`.
+    fn key(&self) -> Result<String, (StatusCode, String)> {
+        Ok(format!("{}/{}", self.container, self.proto()?))
+    }
+
+    fn host_ip(&self, default: IpAddr) -> Result<IpAddr, (StatusCode, String)> {
+        match &self.host_ip {
+            Some(ip) => ip.trim().parse().map_err(|_| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("ports: invalid host_ip {ip:?}"),
+                )
+            }),
+            None => Ok(default),
+        }
+    }
 }
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct VolumeMap {
@@ -41,47 +90,267 @@ pub struct VolumeMap {
     pub ro: Option<bool>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct ContainerInfo {
     pub name: String,
     pub id: String,
+    /// Host port per `port/proto` key, e.g. `"53/udp": 32768`; ports left to
+    /// the daemon are read back after start
     pub ports: std::collections::HashMap<String, u16>,
 }
 
-fn pick_host_port() -> Result<u16, String> {
-    const MAX_ATTEMPTS: u32 = 100;
-    let mut attempts = 0;
-    let mut rng_instance = rng();
+fn parse_cpu(cpu: &str) -> Result<f64, String> {
+    match cpu.trim().parse::<f64>() {
+        Ok(n) if n.is_finite() && n > 0.0 => Ok(n),
+        _ => Err(format!(
+            "invalid value {cpu:?}, expected a positive number of CPUs such as \"1.5\""
+        )),
+    }
+}
+
+/// Parse a size such as `512m`, `1.5gi` or `2048`. Accepts a byte count or
+/// a `k`/`m`/`g` suffix, optionally followed by `b`, `i` or `ib`; like the
+/// Docker CLI, every variant is a binary multiple. Suffixed values may be
+/// fractional.
+fn parse_bytes(s: &str) -> Result<u64, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let split = lower
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(lower.len());
+    let (num, unit) = lower.split_at(split);
+
+    let invalid = || format!("invalid size {s:?}, expected e.g. \"512m\", \"1.5g\" or \"2048\"");
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" | "ki" | "kib" => 1 << 10,
+        "m" | "mb" | "mi" | "mib" => 1 << 20,
+        "g" | "gb" | "gi" | "gib" => 1 << 30,
+        _ => return Err(invalid()),
+    };
 
-    loop {
-        let p: u16 = rng_instance.random_range(20000..=65535);
-        if std::net::TcpListener::bind(("127.0.0.1", p)).is_ok() {
-            return Ok(p);
+    if let Ok(n) = num.parse::<u64>() {
+        return n.checked_mul(multiplier).ok_or_else(invalid);
+    }
+
+    // Fractional values need a unit; the result is rounded down to whole bytes.
+    match num.parse::<f64>() {
+        Ok(n) if multiplier > 1 && n.is_finite() && n >= 0.0 => {
+            let bytes = n * multiplier as f64;
+            if bytes < u64::MAX as f64 {
+                Ok(bytes as u64)
+            } else {
+                Err(invalid())
+            }
         }
-        attempts += 1;
-        if attempts >= MAX_ATTEMPTS {
-            return Err(format!(
-                "Failed to find a free port after {MAX_ATTEMPTS} attempts"
-            ));
+        _ => Err(invalid()),
+    }
+}
+
+/// Set on everything Orqos creates, so its resources can be told apart from
+/// ones started elsewhere on the host.
+pub(crate) const MANAGED_LABEL: &str = "com.orqos.managed";
+/// RFC 3339 time at which Orqos created the resource.
+pub(crate) const CREATED_LABEL: &str = "com.orqos.created";
+
+/// `labels` plus the managed/created labels. Ours win over user-supplied
+/// values of the same key, so `managed=true` can't be faked or dropped.
+pub(crate) fn managed_labels(labels: Option<HashMap<String, String>>) -> HashMap<String, String> {
+    let mut labels = labels.unwrap_or_default();
+    labels.insert(MANAGED_LABEL.into(), "true".into());
+    labels.insert(
+        CREATED_LABEL.into(),
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    labels
+}
+
+/// Parse a `--restart` style policy: `no`, `always`, `unless-stopped` or
+/// `on-failure` with an optional `:N` retry limit.
+pub(crate) fn parse_restart_policy(s: &str) -> Result<RestartPolicy, String> {
+    let (name, retries) = match s.trim().split_once(':') {
+        Some((name, n)) => (name, Some(n)),
+        None => (s.trim(), None),
+    };
+    let invalid =
+        || format!("invalid value {s:?}, expected no, always, unless-stopped or on-failure[:N]");
+
+    let name = match name {
+        "no" => RestartPolicyNameEnum::NO,
+        "always" => RestartPolicyNameEnum::ALWAYS,
+        "unless-stopped" => RestartPolicyNameEnum::UNLESS_STOPPED,
+        "on-failure" => RestartPolicyNameEnum::ON_FAILURE,
+        _ => return Err(invalid()),
+    };
+    let maximum_retry_count = match retries {
+        // Only on-failure takes a retry limit
+        Some(n) if name == RestartPolicyNameEnum::ON_FAILURE => {
+            Some(n.parse::<u32>().map_err(|_| invalid())? as i64)
+        }
+        Some(_) => return Err(invalid()),
+        None => None,
+    };
+
+    Ok(RestartPolicy {
+        name: Some(name),
+        maximum_retry_count,
+    })
+}
+
+/// Log lines attached to the error when a freshly created container fails
+/// to start.
+const START_FAILURE_LOG_LINES: usize = 20;
+
+/// Best-effort tail of a container's combined stdout/stderr; empty on error.
+async fn recent_logs(docker: &Docker, id: &str, lines: usize) -> String {
+    let opts = LogsOptionsBuilder::new()
+        .stdout(true)
+        .stderr(true)
+        .tail(&lines.to_string())
+        .build();
+
+    let mut out = Vec::new();
+    let mut stream = docker.logs(id, Some(opts));
+    while let Some(Ok(frame)) = stream.next().await {
+        out.extend_from_slice(&frame.into_bytes());
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Read `KEY=VALUE` lines from `raw`, which must resolve to a file under
+/// `base`. Blank lines and `#` comments are skipped.
+fn load_env_file(raw: &str, base: Option<&Path>) -> Result<Vec<String>, (StatusCode, String)> {
+    let Some(base) = base else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "env_file is disabled (ORQOS_ENV_FILE_BASE not set)".into(),
+        ));
+    };
+
+    let outside = || {
+        (
+            StatusCode::FORBIDDEN,
+            "env_file outside allowed base directory".to_string(),
+        )
+    };
+    let base_err = |e: std::io::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("ORQOS_ENV_FILE_BASE: {e}"),
+        )
+    };
+    // Check the path as written first, without touching the filesystem, so
+    // a 400 for a missing file can't be used to probe outside the base.
+    let lexical_base = lexical_absolute(base).map_err(base_err)?;
+    if !lexical_absolute(Path::new(raw)).is_ok_and(|p| p.starts_with(&lexical_base)) {
+        return Err(outside());
+    }
+
+    // Then canonicalise both sides so symlinks can't escape the base.
+    let not_found = |e: std::io::Error| (StatusCode::BAD_REQUEST, format!("env_file: {e}"));
+    let base = base.canonicalize().map_err(base_err)?;
+    let path = Path::new(raw).canonicalize().map_err(not_found)?;
+    if !path.starts_with(&base) {
+        return Err(outside());
+    }
+
+    let content = std::fs::read_to_string(&path).map_err(not_found)?;
+    let mut vars = Vec::new();
+    for (no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((key, _)) if !key.is_empty() && !key.contains(char::is_whitespace) => {
+                vars.push(line.to_owned())
+            }
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("env_file line {}: expected KEY=VALUE", no + 1),
+                ))
+            }
+        }
+    }
+    Ok(vars)
+}
+
+/// `path` made absolute with `.` and `..` resolved textually.
+fn lexical_absolute(path: &Path) -> std::io::Result<PathBuf> {
+    let mut out = PathBuf::new();
+    for part in std::path::absolute(path)?.components() {
+        match part {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other),
         }
     }
+    Ok(out)
 }
 
-fn parse_cpu(cpu: &str) -> f64 {
-    cpu.parse().unwrap_or(1.0)
+fn env_key(entry: &str) -> &str {
+    entry.split_once('=').map_or(entry, |(k, _)| k)
 }
 
-fn parse_bytes(s: &str) -> u64 {
-    let s = s.trim().to_lowercase();
-    if let Some(num) = s.strip_suffix("g") {
-        num.parse::<u64>().unwrap_or(1) * 1024 * 1024 * 1024
-    } else if let Some(num) = s.strip_suffix("m") {
-        num.parse::<u64>().unwrap_or(1) * 1024 * 1024
-    } else if let Some(num) = s.strip_suffix("k") {
-        num.parse::<u64>().unwrap_or(1) * 1024
-    } else {
-        s.parse::<u64>().unwrap_or(0)
+/// Validate `KEY=VALUE` / bare `KEY` (pass-through) entries and drop
+/// duplicate keys, keeping the last occurrence in its position.
+fn normalize_env(env: Vec<String>) -> Result<Vec<String>, (StatusCode, String)> {
+    for entry in &env {
+        let key = env_key(entry);
+        if key.is_empty() || key.contains(|c: char| c.is_whitespace() || c == '\0') {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("env: malformed entry {entry:?}, expected KEY=VALUE or KEY"),
+            ));
+        }
     }
+
+    let mut seen = HashSet::new();
+    let mut out: Vec<String> = env
+        .into_iter()
+        .rev()
+        .filter(|e| seen.insert(env_key(e).to_owned()))
+        .collect();
+    out.reverse();
+    Ok(out)
+}
+
+/// Bound the number of port mappings (each makes the daemon claim a host
+/// port and set up forwarding for it), validate their protocols and host IPs
+/// and reject a port/proto pair listed twice, before anything reaches the
+/// daemon.
+fn check_ports(maps: &[PortMap], max: usize) -> Result<(), (StatusCode, String)> {
+    if maps.len() > max {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("ports: {} entries, at most {max} allowed", maps.len()),
+        ));
+    }
+    let mut seen = HashSet::new();
+    for map in maps {
+        let key = map.key()?;
+        map.host_ip(IpAddr::V4(Ipv4Addr::UNSPECIFIED))?;
+        if !seen.insert(key.clone()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("ports: container port {key} listed twice"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parse an optional resource field, naming it in the 400 on failure.
+fn parse_field<T>(
+    field: &str,
+    value: Option<&String>,
+    parse: fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, (StatusCode, String)> {
+    value
+        .map(|v| parse(v).map_err(|e| (StatusCode::BAD_REQUEST, format!("{field}: {e}"))))
+        .transpose()
 }
 
 #[utoipa::path(
@@ -98,9 +367,11 @@ fn parse_bytes(s: &str) -> u64 {
             "memory": "1g",
             "env": ["RUST_LOG=info"],
             "labels": { "tier": "backend" },
+            "restart_policy": "unless-stopped",
             "ports": [
-                { "container": 80, "host": 8080 },
-                { "container": 443 }
+                { "container": 80, "host": 8080, "host_ip": "10.0.0.5" },
+                { "container": 443 },
+                { "container": 53, "proto": "udp" }
             ],
             "network": "my-network",
             "volumes": [
@@ -108,8 +379,16 @@ fn parse_bytes(s: &str) -> u64 {
             ]
         })
     ),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key (and body) get the original result instead of a second container")
+    ),
     responses(
-        (status = 200, description = "Container created", body = ContainerInfo),
+        (status = 200, description = "Container created (or replayed for a known `Idempotency-Key`)", body = ContainerInfo),
+        (status = 400, description = "Unparseable `cpu`, `memory`, `swap` or `restart_policy`, too many or duplicate `ports`, a malformed `env_file` or `Idempotency-Key`"),
+        (status = 403, description = "`env_file` outside `ORQOS_ENV_FILE_BASE` or disabled"),
+        (status = 409, description = "A container with that name already exists"),
+        (status = 413, description = "Request body exceeds `ORQOS_MAX_BODY_BYTES`"),
+        (status = 422, description = "Invalid body, or `Idempotency-Key` reused with a different body"),
         (status = 500, description = "Internal server error"),
     ),
     tag = "Containers",
@@ -118,29 +397,121 @@ fn parse_bytes(s: &str) -> u64 {
 )]
 pub(crate) async fn create_container_handler(
     State(app): State<Arc<AppState>>,
-    Json(req): Json<ContainerCreate>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
 ) -> Result<Json<ContainerInfo>, (StatusCode, String)> {
-    let docker: &Docker = &app.docker;
+    let parse = |body| {
+        serde_json::from_value::<ContainerCreate>(body).map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("invalid body: {e}"),
+            )
+        })
+    };
+
+    let key = match headers.get("idempotency-key") {
+        None => return create_and_start(&app, parse(body)?).await.map(Json),
+        Some(v) => v
+            .to_str()
+            .ok()
+            .map(str::trim)
+            .filter(|k| !k.is_empty() && k.len() <= 255)
+            .ok_or((
+                StatusCode::BAD_REQUEST,
+                "Idempotency-Key must be 1-255 visible ASCII characters".to_string(),
+            ))?
+            .to_owned(),
+    };
+
+    // `Value` objects serialise with sorted keys, so equal bodies hash equal
+    // whatever order the client sent the fields in.
+    let digest: [u8; 32] = Sha256::digest(body.to_string().as_bytes()).into();
+    app.create_idempotency
+        .get_or_run(key, digest, || async {
+            create_and_start(&app, parse(body)?).await
+        })
+        .await
+        .map(Json)
+}
+
+/// Create a container from `req` and start it. Shared by every endpoint that
+/// spins up containers so they all honour the same request semantics.
+pub(crate) async fn create_and_start(
+    app: &AppState,
+    req: ContainerCreate,
+) -> Result<ContainerInfo, (StatusCode, String)> {
+    let prepared = prepare_container(app, req)?;
+    start_prepared(app, prepared).await
+}
+
+/// A validated [`ContainerCreate`], ready to hand to the daemon.
+pub(crate) struct PreparedContainer {
+    name: String,
+    config: ContainerCreateBody,
+    ports: HashMap<String, u16>,
+}
+
+impl PreparedContainer {
+    /// Also answer to `alias` on `network` (which must be the network the
+    /// container joins), the way compose services find each other.
+    pub(crate) fn with_network_alias(mut self, network: &str, alias: &str) -> Self {
+        let endpoint = EndpointSettings {
+            aliases: Some(vec![alias.to_owned()]),
+            ..Default::default()
+        };
+        self.config.networking_config = Some(NetworkingConfig {
+            endpoints_config: Some(HashMap::from([(network.to_owned(), endpoint)])),
+        });
+        self
+    }
+}
+
+/// Validate `req` and turn it into the daemon's create body without
+/// touching Docker, so callers can reject bad input before doing anything
+/// destructive.
+pub(crate) fn prepare_container(
+    app: &AppState,
+    req: ContainerCreate,
+) -> Result<PreparedContainer, (StatusCode, String)> {
     let cname = req.name.clone();
 
+    // Resources: reject typos up front instead of silently defaulting
+    let cpu = parse_field("cpu", req.cpu.as_ref(), parse_cpu)?;
+    let memory = parse_field("memory", req.memory.as_ref(), parse_bytes)?;
+    let swap = parse_field("swap", req.swap.as_ref(), parse_bytes)?;
+    let restart_policy = parse_field(
+        "restart_policy",
+        req.restart_policy.as_ref(),
+        parse_restart_policy,
+    )?;
+
+    // Env: file entries first so the request's own entries win the dedup
+    let mut env = match &req.env_file {
+        Some(file) => load_env_file(file, app.config.env_file_base.as_deref())?,
+        None => Vec::new(),
+    };
+    env.extend(req.env.unwrap_or_default());
+    let env = normalize_env(env)?;
+
     // Ports
     let mut exposed: HashMap<String, HashMap<(), ()>> = HashMap::new();
     let mut bindings: HashMap<String, Option<Vec<PortBinding>>> = HashMap::new();
     let mut port_report: HashMap<String, u16> = HashMap::new();
 
     if let Some(maps) = &req.ports {
-        for &PortMap { container, host } in maps {
-            let key = format!("{}/tcp", container);
-            let host_port = match host {
-                Some(hp) => hp,
-                None => pick_host_port().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
-            };
+        check_ports(maps, app.config.max_ports)?;
+        for map in maps {
+            let key = map.key()?;
+            let host_ip = map.host_ip(app.config.default_bind_ip)?;
+            // 0 (or none) leaves the choice to the daemon, which knows what
+            // is free on *its* host; we read the port back after start.
+            let host_port = map.host.unwrap_or(0);
             exposed.insert(key.clone(), HashMap::new());
             bindings.insert(
                 key.clone(),
                 Some(vec![PortBinding {
-                    host_ip: Some("0.0.0.0".into()),
-                    host_port: Some(host_port.to_string()),
+                    host_ip: Some(host_ip.to_string()),
+                    host_port: (host_port != 0).then(|| host_port.to_string()),
                 }]),
             );
             port_report.insert(key, host_port);
@@ -161,9 +532,10 @@ pub(crate) async fn create_container_handler(
     let host_cfg = HostConfig {
         network_mode: Some(req.network.clone().unwrap_or_else(|| "bridge".into())),
         binds,
-        cpu_quota: req.cpu.as_ref().map(|c| (parse_cpu(c) * 100_000.0) as i64),
-        memory: req.memory.as_ref().map(|m| parse_bytes(m) as i64),
-        memory_swap: req.swap.as_ref().map(|s| parse_bytes(s) as i64),
+        cpu_quota: cpu.map(|c| (c * 100_000.0) as i64),
+        memory: memory.map(|m| m as i64),
+        memory_swap: swap.map(|s| s as i64),
+        restart_policy,
         port_bindings: if bindings.is_empty() {
             None
         } else {
@@ -175,8 +547,8 @@ pub(crate) async fn create_container_handler(
     // Container config
     let cfg = ContainerCreateBody {
         image: Some(req.image),
-        env: req.env,
-        labels: req.labels.clone(),
+        env: (!env.is_empty()).then_some(env),
+        labels: Some(managed_labels(req.labels)),
         exposed_ports: if exposed.is_empty() {
             None
         } else {
@@ -186,27 +558,283 @@ pub(crate) async fn create_container_handler(
         ..Default::default()
     };
 
+    Ok(PreparedContainer {
+        name: cname,
+        config: cfg,
+        ports: port_report,
+    })
+}
+
+/// Create and start a container from [`prepare_container`]'s output.
+pub(crate) async fn start_prepared(
+    app: &AppState,
+    prepared: PreparedContainer,
+) -> Result<ContainerInfo, (StatusCode, String)> {
+    start_prepared_with(app, prepared, |_| {}).await
+}
+
+/// [`start_prepared`], reporting each step (`creating`, `starting`) to
+/// `on_step` before it runs.
+pub(crate) async fn start_prepared_with(
+    app: &AppState,
+    prepared: PreparedContainer,
+    mut on_step: impl FnMut(&'static str),
+) -> Result<ContainerInfo, (StatusCode, String)> {
+    let docker: &Docker = &app.docker;
+    let PreparedContainer {
+        name: cname,
+        config: cfg,
+        ports: port_report,
+    } = prepared;
+
     let opts = CreateContainerOptions {
         name: Some(cname.clone()),
         ..Default::default()
     };
 
+    on_step("creating");
     let resp = docker
         .create_container(Some(opts), cfg)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| match e {
+            // The name is taken
+            bollard::errors::Error::DockerResponseServerError {
+                status_code: 409,
+                message,
+            } => (StatusCode::CONFLICT, message),
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
 
-    docker
+    on_step("starting");
+    if let Err(e) = docker
         .start_container(
             &cname,
             None::<bollard::query_parameters::StartContainerOptions>,
         )
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    {
+        // Entrypoint failures (missing binary, exec format error, ...) only
+        // show up in the container's own output, so attach its tail.
+        let mut msg = e.to_string();
+        let tail = recent_logs(docker, &resp.id, START_FAILURE_LOG_LINES).await;
+        if !tail.trim().is_empty() {
+            msg.push_str("\n--- container logs ---\n");
+            msg.push_str(tail.trim_end());
+        }
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, msg));
+    }
+
+    let mut port_report = port_report;
+    if port_report.values().any(|&p| p == 0) {
+        fill_assigned_ports(docker, &resp.id, &mut port_report).await;
+    }
 
-    Ok(Json(ContainerInfo {
+    Ok(ContainerInfo {
         name: cname,
         id: resp.id,
         ports: port_report,
-    }))
+    })
+}
+
+/// Replace the `0` placeholders in `ports` with the host ports the daemon
+/// picked. Left at `0` (and logged) if the container can't be inspected.
+async fn fill_assigned_ports(docker: &Docker, id: &str, ports: &mut HashMap<String, u16>) {
+    let assigned = match docker
+        .inspect_container(id, None::<InspectContainerOptions>)
+        .await
+    {
+        Ok(info) => info.network_settings.and_then(|n| n.ports),
+        Err(e) => {
+            tracing::warn!(container = id, "could not read assigned host ports: {e}");
+            return;
+        }
+    };
+    let Some(assigned) = assigned else { return };
+
+    for (key, port) in ports.iter_mut().filter(|(_, p)| **p == 0) {
+        // IPv4 and IPv6 bindings share the port; take the first
+        if let Some(p) = assigned
+            .get(key)
+            .and_then(|b| b.as_ref())
+            .into_iter()
+            .flatten()
+            .find_map(|b| b.host_port.as_deref()?.parse().ok())
+        {
+            *port = p;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn normalize_env_keeps_last_duplicate() {
+        let out = normalize_env(env(&["FOO=a", "BAR=1", "FOO=b"])).unwrap();
+        assert_eq!(out, env(&["BAR=1", "FOO=b"]));
+    }
+
+    #[test]
+    fn normalize_env_preserves_order_of_unique_keys() {
+        let out = normalize_env(env(&["A=1", "B=2", "PASS", "C=3", "B=4", "PASS"])).unwrap();
+        assert_eq!(out, env(&["A=1", "C=3", "B=4", "PASS"]));
+    }
+
+    #[test]
+    fn normalize_env_rejects_malformed() {
+        for bad in ["", "=value", "MY KEY=1", " FOO=1"] {
+            assert!(
+                normalize_env(env(&[bad])).is_err(),
+                "{bad:?} should be rejected"
+            );
+        }
+        assert_eq!(normalize_env(env(&["EMPTY="])).unwrap(), env(&["EMPTY="]));
+    }
+
+    #[test]
+    fn env_file_outside_base_is_forbidden_whether_or_not_it_exists() {
+        let base = std::env::temp_dir().join(format!("orqos-envbase-{}", std::process::id()));
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::write(base.join("app.env"), "A=1\n").unwrap();
+
+        let check = |raw: &str| load_env_file(raw, Some(&base)).map_err(|(s, _)| s);
+        let inside = base.join("app.env");
+        let escape = format!("{}/../../etc/passwd", base.display());
+        let missing = format!("{}/../no-such-dir/x.env", base.display());
+        let results = (
+            check(inside.to_str().unwrap()),
+            check(&escape),
+            check(&missing),
+            check(&base.join("nope.env").display().to_string()),
+        );
+        let _ = std::fs::remove_dir_all(&base);
+
+        assert_eq!(results.0, Ok(vec!["A=1".to_string()]));
+        assert_eq!(results.1, Err(StatusCode::FORBIDDEN));
+        assert_eq!(results.2, Err(StatusCode::FORBIDDEN));
+        assert_eq!(results.3, Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn parse_bytes_suffixes() {
+        assert_eq!(parse_bytes("512m"), Ok(512 * 1024 * 1024));
+        assert_eq!(parse_bytes("512MB"), Ok(512 * 1024 * 1024));
+        assert_eq!(parse_bytes("4k"), Ok(4096));
+        assert_eq!(parse_bytes("1gib"), Ok(1 << 30));
+    }
+
+    #[test]
+    fn parse_bytes_fractional() {
+        assert_eq!(parse_bytes("1.5gi"), Ok(3 * 512 * 1024 * 1024));
+        assert_eq!(parse_bytes("0.5k"), Ok(512));
+    }
+
+    #[test]
+    fn parse_bytes_bare_number() {
+        assert_eq!(parse_bytes("2048"), Ok(2048));
+        assert_eq!(parse_bytes(" 2048b "), Ok(2048));
+    }
+
+    #[test]
+    fn parse_bytes_rejects_garbage() {
+        for bad in ["", "g", "1.5", "abc", "1gbb", "1t", "-1m", "1.2.3m", "1 g"] {
+            assert!(parse_bytes(bad).is_err(), "{bad:?} should be rejected");
+        }
+        assert!(parse_bytes("99999999999999999999g").is_err());
+    }
+
+    fn ports(list: &[u16]) -> Vec<PortMap> {
+        list.iter()
+            .map(|&container| PortMap {
+                container,
+                host: None,
+                proto: None,
+                host_ip: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_ports_caps_count() {
+        assert!(check_ports(&ports(&[80, 443]), 2).is_ok());
+        let (status, _) = check_ports(&ports(&[80, 443, 8080]), 2).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn check_ports_rejects_duplicates() {
+        assert!(check_ports(&ports(&[80, 8080, 80]), 64).is_err());
+    }
+
+    #[test]
+    fn check_ports_keys_on_proto() {
+        let mut maps = ports(&[53, 53]);
+        maps[1].proto = Some("udp".into());
+        assert!(check_ports(&maps, 64).is_ok());
+        assert_eq!(maps[1].key().unwrap(), "53/udp");
+
+        maps[1].proto = Some("sctp".into());
+        assert!(check_ports(&maps, 64).is_err());
+    }
+
+    #[test]
+    fn host_ip_defaults_and_validates() {
+        let mut map = ports(&[80]).remove(0);
+        let default = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert_eq!(map.host_ip(default).unwrap(), default);
+
+        map.host_ip = Some("10.0.0.5".into());
+        assert_eq!(map.host_ip(default).unwrap().to_string(), "10.0.0.5");
+        map.host_ip = Some("::1".into());
+        assert!(map.host_ip(default).is_ok());
+
+        map.host_ip = Some("10.0.0".into());
+        assert!(check_ports(&[map], 64).is_err());
+    }
+
+    #[test]
+    fn managed_labels_merge_and_override() {
+        let user = HashMap::from([
+            ("tier".to_string(), "backend".to_string()),
+            (MANAGED_LABEL.to_string(), "false".to_string()),
+        ]);
+        let labels = managed_labels(Some(user));
+        assert_eq!(labels["tier"], "backend");
+        assert_eq!(labels[MANAGED_LABEL], "true");
+        assert!(chrono::DateTime::parse_from_rfc3339(&labels[CREATED_LABEL]).is_ok());
+        assert_eq!(managed_labels(None).len(), 2);
+    }
+
+    #[test]
+    fn parse_restart_policy_names() {
+        let p = parse_restart_policy("unless-stopped").unwrap();
+        assert_eq!(p.name, Some(RestartPolicyNameEnum::UNLESS_STOPPED));
+        assert_eq!(p.maximum_retry_count, None);
+
+        let p = parse_restart_policy("on-failure:3").unwrap();
+        assert_eq!(p.name, Some(RestartPolicyNameEnum::ON_FAILURE));
+        assert_eq!(p.maximum_retry_count, Some(3));
+    }
+
+    #[test]
+    fn parse_restart_policy_rejects_garbage() {
+        for bad in [
+            "",
+            "sometimes",
+            "always:3",
+            "on-failure:",
+            "on-failure:-1",
+            "No",
+        ] {
+            assert!(
+                parse_restart_policy(bad).is_err(),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
 }
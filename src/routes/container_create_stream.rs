@@ -0,0 +1,113 @@
+use std::{convert::Infallible, pin::pin, sync::Arc};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Json, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use bollard::{
+    errors::Error as BollardError,
+    query_parameters::{CreateImageOptions, CreateImageOptionsBuilder},
+};
+use futures::channel::mpsc::UnboundedSender;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+
+use crate::{
+    routes::{
+        container_create::{
+            prepare_container, start_prepared_with, ContainerCreate, ContainerInfo,
+            PreparedContainer,
+        },
+        images::{map_image_err, split_image_ref, validate_image_ref},
+    },
+    state::AppState,
+};
+
+/// Create and start a container like `POST /containers`, pulling the image
+/// first if it isn't present, and report progress as NDJSON events:
+/// `pulling`, `pull-progress` (the daemon's message under `progress`),
+/// `creating`, `starting`, then `started` with the `ContainerInfo` under
+/// `container`, or `error` with a message.
+///
+/// The body is validated before the stream starts, so bad input still gets a
+/// proper status code. Once started, the work continues even if the client
+/// disconnects.
+#[utoipa::path(
+    post,
+    path = "/containers/stream",
+    request_body = ContainerCreate,
+    responses(
+        (status = 200, description = "Progress events, one JSON object per line", content_type = "application/x-ndjson", body = String),
+        (status = 400, description = "Invalid image reference or create body"),
+        (status = 403, description = "`env_file` outside `ORQOS_ENV_FILE_BASE` or disabled"),
+        (status = 413, description = "Request body exceeds `ORQOS_MAX_BODY_BYTES`"),
+    ),
+    tag = "Containers",
+    summary = "Create and start a container, streaming pull/create/start progress",
+)]
+pub async fn create_container_stream_handler(
+    State(app): State<Arc<AppState>>,
+    Json(req): Json<ContainerCreate>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    validate_image_ref(&req.image)?;
+    let image = req.image.clone();
+    let prepared = prepare_container(&app, req)?;
+
+    let (tx, rx) = futures::channel::mpsc::unbounded::<Value>();
+    tokio::spawn(async move {
+        let last = match pull_create_start(&app, &image, prepared, &tx).await {
+            Ok(info) => json!({ "status": "started", "container": info }),
+            Err((_, msg)) => json!({ "status": "error", "error": msg }),
+        };
+        let _ = tx.unbounded_send(last);
+    });
+
+    let lines = rx.map(|event| Ok::<_, Infallible>(Bytes::from(format!("{event}\n"))));
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    ))
+}
+
+async fn pull_create_start(
+    app: &AppState,
+    image: &str,
+    prepared: PreparedContainer,
+    tx: &UnboundedSender<Value>,
+) -> Result<ContainerInfo, (StatusCode, String)> {
+    // A gone client just means nobody reads the events any more
+    let send = |event: Value| {
+        let _ = tx.unbounded_send(event);
+    };
+
+    match app.docker.inspect_image(image).await {
+        Ok(_) => {}
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {
+            send(json!({ "status": "pulling", "image": image }));
+            let mut progress = pin!(app
+                .docker
+                .create_image(Some(pull_options(image)), None, None));
+            while let Some(item) = progress.next().await {
+                let info = item.map_err(map_image_err)?;
+                send(json!({ "status": "pull-progress", "progress": info }));
+            }
+        }
+        Err(e) => return Err(map_image_err(e)),
+    }
+
+    start_prepared_with(app, prepared, |step| send(json!({ "status": step }))).await
+}
+
+/// Pull exactly the referenced tag or digest; without one the daemon would
+/// fetch every tag of the repository.
+fn pull_options(image: &str) -> CreateImageOptions {
+    let parts = split_image_ref(image);
+    CreateImageOptionsBuilder::new()
+        .from_image(parts.repo)
+        .tag(parts.reference())
+        .build()
+}
@@ -0,0 +1,68 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use bollard::query_parameters::InspectContainerOptions;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    redact::{redact_env_entry, redact_labels},
+    routes::{container_id::validate_container_id, exec::map_docker_err},
+    state::AppState,
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContainerEnvResponse {
+    /// `KEY=VALUE` entries as configured, sensitive values masked
+    pub env: Vec<String>,
+    pub labels: HashMap<String, String>,
+}
+
+/// The container's configured env vars and labels, without the rest of the
+/// inspect output. Values of keys matching `ORQOS_SENSITIVE_KEYS` (default
+/// `*_TOKEN,*_PASSWORD,*_KEY`) are replaced with `***` unless
+/// `ORQOS_REDACT_ENV=off`.
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/env",
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    responses(
+        (status = 200, description = "Env and labels", body = ContainerEnvResponse),
+        (status = 400, description = "Invalid container ID"),
+        (status = 404, description = "Container not found"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+)]
+pub async fn container_env_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+) -> Result<Json<ContainerEnvResponse>, (StatusCode, String)> {
+    let container =
+        validate_container_id(&container).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let config = state
+        .docker
+        .inspect_container(container, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?
+        .config
+        .unwrap_or_default();
+
+    let sensitive = state.config.sensitive_keys.as_ref();
+    let env = config
+        .env
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| redact_env_entry(sensitive, entry))
+        .collect();
+    let mut labels = config.labels.unwrap_or_default();
+    redact_labels(sensitive, &mut labels);
+
+    Ok(Json(ContainerEnvResponse { env, labels }))
+}
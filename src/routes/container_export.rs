@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use bollard::{models::ContainerInspectResponse, query_parameters::InspectContainerOptions};
+
+use crate::{
+    routes::{container_id::validate_container_id, exec::map_docker_err},
+    state::AppState,
+};
+
+/// Stream the container's whole filesystem as a tar archive.
+///
+/// The archive is piped straight from the daemon, never buffered in memory.
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/export",
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    responses(
+        (status = 200, description = "Filesystem tarball", content_type = "application/x-tar", body = Vec<u8>),
+        (status = 400, description = "Invalid container ID"),
+        (status = 404, description = "Container not found"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+)]
+pub async fn export_container_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let container = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_owned();
+
+    // Inspect first: a missing container becomes a clean 404 instead of a
+    // stream that dies after the 200 has been sent, and we get the name.
+    let info = state
+        .docker
+        .inspect_container(&container, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?;
+
+    let filename = attachment_name(&info, &container);
+    let stream = state.docker.export_container(&container);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-tar".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}.tar\""),
+            ),
+        ],
+        Body::from_stream(stream),
+    ))
+}
+
+/// The container's name (or `fallback`), made safe for a
+/// `Content-Disposition` filename.
+pub(crate) fn attachment_name(info: &ContainerInspectResponse, fallback: &str) -> String {
+    let name = info
+        .name
+        .as_deref()
+        .map(|n| n.trim_start_matches('/'))
+        .filter(|n| !n.is_empty())
+        .unwrap_or(fallback);
+    // Names are [a-zA-Z0-9_.-] already, but keep the header safe regardless.
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_.-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
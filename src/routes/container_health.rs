@@ -0,0 +1,161 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::IntoResponse,
+};
+use bollard::{
+    models::{ContainerInspectResponse, HealthStatusEnum},
+    query_parameters::InspectContainerOptions,
+};
+use futures::SinkExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::{
+    routes::{container_id::validate_container_id, exec::map_docker_err},
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HealthQuery {
+    /// Seconds between inspect polls (1–60, default 2)
+    #[param(required = false)]
+    interval: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct HealthUpdate {
+    status: String,
+    failing_streak: i64,
+    /// Output of the most recent probe
+    last_output: Option<String>,
+}
+
+/// The health part of an inspect, plus whether the container still runs.
+/// `None` if it has no HEALTHCHECK.
+fn health_of(info: ContainerInspectResponse) -> Option<(HealthUpdate, bool)> {
+    let state = info.state?;
+    let health = state.health?;
+    let status = health.status.filter(|s| *s != HealthStatusEnum::EMPTY)?;
+    if status == HealthStatusEnum::NONE {
+        return None;
+    }
+
+    let update = HealthUpdate {
+        status: status.to_string(),
+        failing_streak: health.failing_streak.unwrap_or(0),
+        last_output: health
+            .log
+            .and_then(|log| log.into_iter().last())
+            .and_then(|r| r.output)
+            .map(|o| o.trim_end().to_owned()),
+    };
+    Some((update, state.running.unwrap_or(false)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/health/ws",
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        HealthQuery,
+    ),
+    responses(
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 400, description = "Invalid container ID"),
+        (status = 404, description = "Container not found"),
+        (status = 409, description = "Container has no HEALTHCHECK"),
+    ),
+    tag = "Streaming",
+    summary = "Follow a container's health status over WebSocket",
+    description = "Sends the current `{\"status\", \"failing_streak\", \"last_output\"}` on connect, then again whenever it changes. Closes when the container stops or the client disconnects."
+)]
+pub async fn health_ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<HealthQuery>,
+) -> impl IntoResponse {
+    let container = match validate_container_id(&container) {
+        Ok(id) => id.to_owned(),
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    // Check up front so a missing container or healthcheck gets a status code
+    let info = match state
+        .docker
+        .inspect_container(&container, None::<InspectContainerOptions>)
+        .await
+    {
+        Ok(info) => info,
+        Err(e) => return map_docker_err(e).into_response(),
+    };
+    if health_of(info).is_none() {
+        return (
+            StatusCode::CONFLICT,
+            format!("container {container} has no HEALTHCHECK"),
+        )
+            .into_response();
+    }
+
+    let every = Duration::from_secs(q.interval.unwrap_or(2).clamp(1, 60));
+    ws.on_upgrade(move |socket| follow_health(socket, state, container, every))
+}
+
+async fn follow_health(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    container: String,
+    every: Duration,
+) {
+    let mut ticker = interval(every);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last: Option<HealthUpdate> = None;
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        }
+
+        let info = match state
+            .docker
+            .inspect_container(&container, None::<InspectContainerOptions>)
+            .await
+        {
+            Ok(info) => info,
+            Err(e) => {
+                let _ = socket
+                    .send(json!({ "error": e.to_string() }).to_string().into())
+                    .await;
+                break;
+            }
+        };
+        let Some((update, running)) = health_of(info) else {
+            break;
+        };
+
+        if last.as_ref() != Some(&update) {
+            let payload = serde_json::to_string(&update).unwrap_or_default();
+            if socket.send(Message::Text(payload.into())).await.is_err() {
+                return; // client went away
+            }
+            last = Some(update);
+        }
+        if !running {
+            break;
+        }
+    }
+
+    let _ = socket.close().await;
+}
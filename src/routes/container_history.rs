@@ -0,0 +1,78 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::metric_registry::MAX_WINDOW;
+use crate::routes::container_stats::METRICS_DISABLED;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// `cpu` (default) or `mem`
+    #[param(required = false)]
+    metric: Option<String>,
+    /// Seconds of history (default and cap: 60, the registry's retention)
+    #[param(required = false)]
+    window: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContainerHistory {
+    pub id: String,
+    pub metric: String,
+    pub window_secs: u64,
+    /// `[epoch_ms, value]` pairs, oldest first
+    pub samples: Vec<(u64, f64)>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/history",
+    params(
+        ("id" = String, Path, description = "Container ID or name"),
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Raw samples for sparklines", body = ContainerHistory),
+        (status = 400, description = "Unknown metric"),
+        (status = 404, description = "No samples recorded for this container yet, or metrics are disabled"),
+    ),
+    tag = "Containers",
+)]
+pub async fn container_history_handler(
+    State(app): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<HistoryQuery>,
+) -> Result<Json<ContainerHistory>, (StatusCode, String)> {
+    if !app.config.metrics_enabled {
+        return Err((StatusCode::NOT_FOUND, METRICS_DISABLED.into()));
+    }
+    let registry = &app.metric_registry;
+    let window = Duration::from_secs(q.window.unwrap_or(60)).min(MAX_WINDOW);
+    let metric = q.metric.unwrap_or_else(|| "cpu".into());
+
+    let Some(id) = registry.resolve_id(&container) else {
+        return Err((StatusCode::NOT_FOUND, "no samples for container".into()));
+    };
+
+    let samples = match metric.as_str() {
+        "cpu" => registry.cpu_series(&id, window),
+        "mem" => registry.mem_series(&id, window),
+        _ => return Err((StatusCode::BAD_REQUEST, "metric must be cpu or mem".into())),
+    }
+    .unwrap_or_default();
+
+    Ok(Json(ContainerHistory {
+        id,
+        metric,
+        window_secs: window.as_secs(),
+        samples,
+    }))
+}
@@ -0,0 +1,67 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    // Full/short IDs and names, including long compose-style names. Docker
+    // reports names with a leading `/`, so accept (and strip) one.
+    static ref CONTAINER_ID_RE: Regex = Regex::new(r"^/?[a-zA-Z0-9_.-]{1,128}$").unwrap();
+}
+
+/// Validate a container ID or name from a request path, returning it without
+/// any leading `/` so it can be handed straight to the daemon.
+pub(crate) fn validate_container_id(id: &str) -> Result<&str, &'static str> {
+    if CONTAINER_ID_RE.is_match(id) {
+        Ok(id.trim_start_matches('/'))
+    } else {
+        Err("Invalid container ID format")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_ids_and_compose_names() {
+        assert_eq!(
+            validate_container_id("myproject_web_1"),
+            Ok("myproject_web_1")
+        );
+        assert_eq!(
+            validate_container_id("myproject-web-1"),
+            Ok("myproject-web-1")
+        );
+        assert_eq!(validate_container_id("3f2a9c1b7d4e"), Ok("3f2a9c1b7d4e"));
+        assert_eq!(
+            validate_container_id(&"a".repeat(64)),
+            Ok("a".repeat(64).as_str())
+        );
+    }
+
+    #[test]
+    fn strips_leading_slash() {
+        assert_eq!(
+            validate_container_id("/myproject_web_1"),
+            Ok("myproject_web_1")
+        );
+    }
+
+    #[test]
+    fn allows_up_to_128_chars() {
+        let long = format!("my-very-long-compose-project_{}_1", "slug".repeat(24));
+        assert!(long.len() <= 128);
+        assert!(validate_container_id(&long).is_ok());
+        assert!(validate_container_id(&"a".repeat(128)).is_ok());
+        assert!(validate_container_id(&"a".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        for bad in ["", "/", "//web", "web/1", "web 1", "web;rm", "../etc"] {
+            assert!(
+                validate_container_id(bad).is_err(),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+}
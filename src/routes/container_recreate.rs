@@ -0,0 +1,168 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use bollard::{
+    errors::Error as BollardError,
+    query_parameters::{
+        InspectContainerOptions, RemoveContainerOptions, RenameContainerOptions,
+        StopContainerOptions,
+    },
+};
+
+use crate::{
+    routes::{
+        container_create::{prepare_container, start_prepared, ContainerCreate, ContainerInfo},
+        container_id::validate_container_id,
+        exec::map_docker_err,
+    },
+    state::AppState,
+};
+
+#[derive(Debug, Default, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecreateQuery {
+    /// Seconds to wait for the old container to stop (capped by
+    /// `ORQOS_MAX_STOP_TIMEOUT`, default 5)
+    #[param(required = false)]
+    t: Option<u64>,
+    /// Rename the old container aside instead of removing it up front; it is
+    /// removed once the new one starts, and kept if that fails
+    #[param(required = false)]
+    keep_backup: Option<bool>,
+}
+
+/// Replace a container with a fresh one of the same name: stop and remove
+/// (or rename aside) the old one, then create and start from the body.
+///
+/// The body is fully validated before the old container is touched. The
+/// body's `name` is ignored; the existing container's name is reused.
+#[utoipa::path(
+    post,
+    path = "/containers/{id}/recreate",
+    request_body = ContainerCreate,
+    params(
+        ("id" = String, Path, description = "Container ID or name"),
+        RecreateQuery,
+    ),
+    responses(
+        (status = 200, description = "Container recreated", body = ContainerInfo),
+        (status = 400, description = "Invalid container ID or create body"),
+        (status = 404, description = "Container not found"),
+        (status = 413, description = "Request body exceeds `ORQOS_MAX_BODY_BYTES`"),
+        (status = 500, description = "Docker or server error; says whether the old container is gone or where it was kept", body = String)
+    ),
+    tag = "Containers",
+)]
+pub async fn recreate_container_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<RecreateQuery>,
+    Json(mut req): Json<ContainerCreate>,
+) -> Result<Json<ContainerInfo>, (StatusCode, String)> {
+    let container = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_owned();
+
+    let info = state
+        .docker
+        .inspect_container(&container, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?;
+    let old_id = info.id.unwrap_or(container);
+    let name = info
+        .name
+        .as_deref()
+        .map(|n| n.trim_start_matches('/').to_owned())
+        .unwrap_or_default();
+    if name.is_empty() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("container {old_id} has no name to reuse"),
+        ));
+    }
+
+    req.name = name.clone();
+    let prepared = prepare_container(&state, req)?;
+
+    let t = q.t.unwrap_or(5).min(state.config.max_stop_timeout);
+    match state
+        .docker
+        .stop_container(
+            &old_id,
+            Some(StopContainerOptions {
+                t: Some(t as i32),
+                signal: None,
+            }),
+        )
+        .await
+    {
+        // 304: already stopped
+        Ok(())
+        | Err(BollardError::DockerResponseServerError {
+            status_code: 304, ..
+        }) => {}
+        Err(e) => return Err(map_docker_err(e)),
+    }
+
+    let backup = if q.keep_backup.unwrap_or(false) {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let backup = format!("{name}.bak-{secs}");
+        state
+            .docker
+            .rename_container(
+                &old_id,
+                RenameContainerOptions {
+                    name: backup.clone(),
+                },
+            )
+            .await
+            .map_err(map_docker_err)?;
+        Some(backup)
+    } else {
+        remove(&state, &old_id).await.map_err(map_docker_err)?;
+        None
+    };
+
+    let created = match start_prepared(&state, prepared).await {
+        Ok(created) => created,
+        Err((status, msg)) => {
+            let fate = match &backup {
+                Some(backup) => format!("the previous container is kept (stopped) as {backup}"),
+                None => format!("the previous container {name} has been removed"),
+            };
+            tracing::error!(container = name, "recreate failed: {msg}");
+            return Err((status, format!("recreate failed, {fate}: {msg}")));
+        }
+    };
+
+    if let Some(backup) = backup {
+        if let Err(e) = remove(&state, &old_id).await {
+            tracing::warn!("recreated {name} but could not remove backup {backup}: {e}");
+        }
+    }
+
+    tracing::info!(container = name, id = created.id, "recreated container");
+    Ok(Json(created))
+}
+
+async fn remove(state: &AppState, id: &str) -> Result<(), BollardError> {
+    state
+        .docker
+        .remove_container(
+            id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await
+}
@@ -18,7 +18,7 @@ pub struct RemoveContainerRequest {
 
 #[utoipa::path(
     post,
-    path = "/containers/:id/remove",
+    path = "/containers/{id}/remove",
     params(
         ("id" = String, Path, description = "Container ID or name")
     ),
@@ -43,9 +43,9 @@ pub async fn remove_container_handler(
 
     match remove_container(&state.docker, &container_id, force, v).await {
         Ok(()) => StatusCode::NO_CONTENT,
-        Err(BollardError::DockerResponseServerError { status_code, .. }) if status_code == 404 => {
-            StatusCode::NOT_FOUND
-        }
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => StatusCode::NOT_FOUND,
         Err(e) => {
             tracing::error!("failed to remove container {container_id}: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
@@ -53,7 +53,7 @@ pub async fn remove_container_handler(
     }
 }
 
-async fn remove_container(
+pub(crate) async fn remove_container(
     docker: &bollard::Docker,
     id: &str,
     force: Option<bool>,
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use bollard::{errors::Error as BollardError, models::ContainerUpdateBody};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    routes::{
+        container_create::parse_restart_policy, container_id::validate_container_id,
+        exec::map_docker_err,
+    },
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RestartPolicyRequest {
+    /// `no`, `always`, `unless-stopped` or `on-failure[:N]`
+    pub policy: String,
+}
+
+/// Change a container's restart policy in place, without recreating it.
+#[utoipa::path(
+    put,
+    path = "/containers/{id}/restart-policy",
+    request_body = RestartPolicyRequest,
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    responses(
+        (status = 204, description = "Restart policy updated"),
+        (status = 400, description = "Invalid container ID or policy, or rejected by the daemon (e.g. auto-remove containers)"),
+        (status = 404, description = "Container not found"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+)]
+pub async fn update_restart_policy_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Json(req): Json<RestartPolicyRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let container =
+        validate_container_id(&container).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let policy = parse_restart_policy(&req.policy)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("policy: {e}")))?;
+
+    state
+        .docker
+        .update_container(
+            container,
+            ContainerUpdateBody {
+                restart_policy: Some(policy),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| match e {
+            BollardError::DockerResponseServerError {
+                status_code: 400,
+                message,
+            } => (StatusCode::BAD_REQUEST, message),
+            e => map_docker_err(e),
+        })?;
+
+    tracing::info!(container, policy = req.policy, "updated restart policy");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,84 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::metric_registry::MAX_WINDOW;
+use crate::state::AppState;
+
+/// Body of the 404 every stats endpoint returns under `ORQOS_METRICS=off`.
+pub(crate) const METRICS_DISABLED: &str = "metrics are disabled (ORQOS_METRICS=off)";
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    /// Aggregation window in seconds (default 10, capped at 60)
+    #[param(required = false)]
+    window: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContainerStats {
+    pub id: String,
+    pub name: String,
+    pub window_secs: u64,
+    pub cpu_avg: Option<f64>,
+    pub mem_max: Option<u64>,
+    /// Bytes per second
+    pub net_rx_rate: Option<f64>,
+    pub net_tx_rate: Option<f64>,
+    pub blk_read_rate: Option<f64>,
+    pub blk_write_rate: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/stats",
+    params(
+        ("id" = String, Path, description = "Container ID or name"),
+        StatsQuery,
+    ),
+    responses(
+        (status = 200, description = "Aggregated resource usage", body = ContainerStats),
+        (status = 404, description = "No samples recorded for this container yet, or metrics are disabled"),
+    ),
+    tag = "Containers",
+)]
+pub async fn container_stats_handler(
+    State(app): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<StatsQuery>,
+) -> Result<Json<ContainerStats>, (StatusCode, String)> {
+    if !app.config.metrics_enabled {
+        return Err((StatusCode::NOT_FOUND, METRICS_DISABLED.into()));
+    }
+    let registry = &app.metric_registry;
+    let window = Duration::from_secs(q.window.unwrap_or(10)).min(MAX_WINDOW);
+
+    let Some(id) = registry.resolve_id(&container) else {
+        return Err((StatusCode::NOT_FOUND, "no samples for container".into()));
+    };
+
+    let stats = ContainerStats {
+        name: registry.display_name(&id),
+        window_secs: window.as_secs(),
+        cpu_avg: registry.cpu_avg(&id, window),
+        mem_max: registry.mem_max(&id, window),
+        net_rx_rate: registry.net_rx_rate(&id, window),
+        net_tx_rate: registry.net_tx_rate(&id, window),
+        blk_read_rate: registry.blk_read_rate(&id, window),
+        blk_write_rate: registry.blk_write_rate(&id, window),
+        id,
+    };
+
+    if stats.cpu_avg.is_none() && stats.mem_max.is_none() {
+        return Err((StatusCode::NOT_FOUND, "no samples in window".into()));
+    }
+
+    Ok(Json(stats))
+}
@@ -1,12 +1,13 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use bollard::errors::Error as BollardError;
-use bollard::query_parameters::StopContainerOptions;
+use bollard::query_parameters::{StopContainerOptions, WaitContainerOptionsBuilder};
+use futures_util::StreamExt;
 use serde::Deserialize;
-use std::sync::Arc;
+use std::{pin::pin, sync::Arc, time::Duration};
 use utoipa::ToSchema;
 
 use crate::state::AppState;
@@ -17,36 +18,66 @@ pub struct StopContainerRequest {
     pub signal: Option<String>,
 }
 
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StopQuery {
+    /// Return only once the container has actually stopped
+    #[param(required = false)]
+    wait: Option<bool>,
+}
+
+/// Slack on top of the stop timeout for `?wait=true`, covering the kill
+/// after the grace period and the daemon's own bookkeeping.
+const STOP_WAIT_MARGIN: Duration = Duration::from_secs(5);
+
 #[utoipa::path(
     post,
-    path = "/containers/:id/stop",
+    path = "/containers/{id}/stop",
     params(
-        ("id" = String, Path, description = "Container ID or name")
+        ("id" = String, Path, description = "Container ID or name"),
+        StopQuery,
     ),
     request_body(content = StopContainerRequest, description = "Stop options", content_type = "application/json"),
     responses(
         (status = 204, description = "Container stopped successfully"),
         (status = 404, description = "Container not found"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 504, description = "With `wait`, the container was still running after the stop timeout")
     ),
     tag = "Containers",
 )]
 pub async fn stop_container_handler(
     State(state): State<Arc<AppState>>,
     Path(container_id): Path<String>,
+    Query(q): Query<StopQuery>,
     maybe_json: Option<Json<StopContainerRequest>>,
 ) -> StatusCode {
     let (t, signal) = maybe_json
         .map(|Json(req)| (req.t, req.signal))
         .unwrap_or((Some(5), None));
 
-    let capped_t = t.map(|v| v.min(60));
+    let capped_t = t.map(|v| v.min(state.config.max_stop_timeout));
 
     match stop_container(&state.docker, &container_id, capped_t, signal).await {
-        Ok(()) => StatusCode::NO_CONTENT,
-        Err(BollardError::DockerResponseServerError { status_code, .. }) if status_code == 404 => {
-            StatusCode::NOT_FOUND
+        Ok(()) if q.wait.unwrap_or(false) => {
+            // Docker's own default when no timeout is given is 10s
+            let bound = Duration::from_secs(capped_t.unwrap_or(10)) + STOP_WAIT_MARGIN;
+            match tokio::time::timeout(bound, wait_stopped(&state.docker, &container_id)).await {
+                Ok(Ok(())) => StatusCode::NO_CONTENT,
+                Ok(Err(e)) => {
+                    tracing::error!("failed waiting for container {container_id} to stop: {e}");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+                Err(_) => {
+                    tracing::warn!("container {container_id} still running after {bound:?}");
+                    StatusCode::GATEWAY_TIMEOUT
+                }
+            }
         }
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => StatusCode::NOT_FOUND,
         Err(e) => {
             tracing::error!("failed to stop container {container_id}: {e}");
             StatusCode::INTERNAL_SERVER_ERROR
@@ -54,7 +85,7 @@ pub async fn stop_container_handler(
     }
 }
 
-async fn stop_container(
+pub(crate) async fn stop_container(
     docker: &bollard::Docker,
     id: &str,
     t: Option<u64>,
@@ -65,3 +96,17 @@ async fn stop_container(
         .stop_container(id, Some(StopContainerOptions { t: t_i32, signal }))
         .await
 }
+
+/// Resolve once `id` is no longer running.
+async fn wait_stopped(docker: &bollard::Docker, id: &str) -> Result<(), BollardError> {
+    let opts = WaitContainerOptionsBuilder::new()
+        .condition("not-running")
+        .build();
+    let mut stream = pin!(docker.wait_container(id, Some(opts)));
+    match stream.next().await {
+        None | Some(Ok(_)) => Ok(()),
+        // A non-zero exit code is reported as an error, but it's still down
+        Some(Err(BollardError::DockerContainerWaitError { .. })) => Ok(()),
+        Some(Err(e)) => Err(e),
+    }
+}
@@ -0,0 +1,238 @@
+//! Bulk container operations
+//! -----------------------------------------------------------
+//! * REST   POST /containers/stop     → stop every matching container
+//! * REST   POST /containers/remove   → remove every matching container
+//!
+//! Matching uses the same `label`/`status`/`name`/`image` filters as
+//! `GET /containers`; at least one is required so an empty query can't hit
+//! everything. Only containers Orqos created are touched unless the caller
+//! passes `managed_only=false`. `plan=true` lists what would be hit, in the
+//! same shape, without acting.
+//! -----------------------------------------------------------
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+use bollard::{
+    errors::Error as BollardError, models::ContainerSummary,
+    query_parameters::ListContainersOptionsBuilder,
+};
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    retry::with_retry,
+    routes::{
+        container_create::MANAGED_LABEL,
+        container_remove::{remove_container, RemoveContainerRequest},
+        container_stop::{stop_container, StopContainerRequest},
+        containers_list::{build_filters, first_name},
+    },
+    state::AppState,
+};
+
+/// Containers acted on at once.
+const CONCURRENCY: usize = 8;
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BulkQuery {
+    /// `k=v` pairs, comma-separated
+    #[param(required = false)]
+    label: Option<String>,
+    #[param(required = false)]
+    status: Option<String>,
+    #[param(required = false)]
+    name: Option<String>,
+    #[param(required = false)]
+    image: Option<String>,
+    /// Only act on containers Orqos created (`com.orqos.managed=true`);
+    /// default `true`. Set `false` to include ones started by other tools.
+    #[param(required = false)]
+    managed_only: Option<bool>,
+    /// Only report the containers that would be affected (status
+    /// `planned`); nothing is stopped or removed
+    #[param(required = false)]
+    plan: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkResult {
+    pub id: String,
+    pub name: String,
+    /// Container state when it was listed, e.g. `running` or `exited`
+    pub state: Option<String>,
+    /// `"ok"`, `"error"`, or `"planned"` for `plan=true`
+    pub status: &'static str,
+    pub error: Option<String>,
+}
+
+impl BulkResult {
+    fn new(c: &ContainerSummary) -> Self {
+        Self {
+            id: c.id.clone().unwrap_or_default(),
+            name: first_name(c).trim_start_matches('/').to_owned(),
+            state: c.state.map(|s| s.to_string()),
+            status: "planned",
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkResponse {
+    pub results: Vec<BulkResult>,
+}
+
+/// Containers matching `q`; running ones only unless `all`.
+async fn matching(
+    state: &AppState,
+    q: &BulkQuery,
+    all: bool,
+) -> Result<Vec<ContainerSummary>, (StatusCode, String)> {
+    let mut filters = build_filters(
+        q.label.as_deref(),
+        q.status.as_deref(),
+        q.name.as_deref(),
+        q.image.as_deref(),
+    );
+    if filters.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "at least one of label, status, name or image is required".into(),
+        ));
+    }
+    if q.managed_only.unwrap_or(true) {
+        filters
+            .entry("label")
+            .or_default()
+            .push(format!("{MANAGED_LABEL}=true"));
+    }
+
+    let opts = ListContainersOptionsBuilder::new()
+        .all(all)
+        .filters(&filters)
+        .build();
+    with_retry("list_containers", state.config.docker_retries, || {
+        state.docker.list_containers(Some(opts.clone()))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// What a bulk call would act on, for `plan=true`.
+fn plan(containers: &[ContainerSummary]) -> BulkResponse {
+    BulkResponse {
+        results: containers.iter().map(BulkResult::new).collect(),
+    }
+}
+
+/// Run `op` on every container, a few at a time, collecting per-container
+/// outcomes in listing order.
+pub(crate) async fn for_each<F, Fut>(containers: Vec<ContainerSummary>, op: F) -> Vec<BulkResult>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), bollard::errors::Error>>,
+{
+    stream::iter(containers.into_iter().map(|c| {
+        let result = BulkResult::new(&c);
+        let fut = op(result.id.clone());
+        async move {
+            let error = fut.await.err().map(|e| e.to_string());
+            BulkResult {
+                status: if error.is_none() { "ok" } else { "error" },
+                error,
+                ..result
+            }
+        }
+    }))
+    .buffered(CONCURRENCY)
+    .collect()
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/containers/stop",
+    params(BulkQuery),
+    request_body(content = StopContainerRequest, description = "Stop options", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Per-container results (`planned` with `plan=true`)", body = BulkResponse),
+        (status = 400, description = "No filter given"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+    summary = "Stop every running container matching the filters",
+)]
+pub async fn bulk_stop_handler(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<BulkQuery>,
+    maybe_json: Option<Json<StopContainerRequest>>,
+) -> Result<Json<BulkResponse>, (StatusCode, String)> {
+    let (t, signal) = maybe_json
+        .map(|Json(req)| (req.t, req.signal))
+        .unwrap_or((Some(5), None));
+    let t = t.map(|v| v.min(state.config.max_stop_timeout));
+
+    let containers = matching(&state, &q, false).await?;
+    if q.plan.unwrap_or(false) {
+        return Ok(Json(plan(&containers)));
+    }
+    tracing::info!(count = containers.len(), ?q, "bulk stop");
+    let results = for_each(containers, |id| {
+        let state = state.clone();
+        let signal = signal.clone();
+        async move {
+            match stop_container(&state.docker, &id, t, signal).await {
+                // 304: stopped since it was listed
+                Err(BollardError::DockerResponseServerError {
+                    status_code: 304, ..
+                }) => Ok(()),
+                res => res,
+            }
+        }
+    })
+    .await;
+
+    Ok(Json(BulkResponse { results }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/containers/remove",
+    params(BulkQuery),
+    request_body(content = RemoveContainerRequest, description = "Options", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Per-container results (`planned` with `plan=true`)", body = BulkResponse),
+        (status = 400, description = "No filter given"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+    summary = "Remove every container (running or not) matching the filters",
+)]
+pub async fn bulk_remove_handler(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<BulkQuery>,
+    maybe_json: Option<Json<RemoveContainerRequest>>,
+) -> Result<Json<BulkResponse>, (StatusCode, String)> {
+    let (force, v) = maybe_json
+        .map(|Json(req)| (req.force, req.v))
+        .unwrap_or((None, None));
+
+    let containers = matching(&state, &q, true).await?;
+    if q.plan.unwrap_or(false) {
+        return Ok(Json(plan(&containers)));
+    }
+    tracing::info!(count = containers.len(), ?q, "bulk remove");
+    let results = for_each(containers, |id| {
+        let state = state.clone();
+        async move { remove_container(&state.docker, &id, force, v).await }
+    })
+    .await;
+
+    Ok(Json(BulkResponse { results }))
+}
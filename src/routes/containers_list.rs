@@ -5,8 +5,12 @@ use axum::response::IntoResponse;
 use axum::Json;
 use bollard::models::ContainerSummary;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::redact::redact_labels;
+use crate::retry::with_retry;
+use crate::routes::container_create::MANAGED_LABEL;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
@@ -19,7 +23,26 @@ pub struct ContainerQuery {
     #[param(required = false)]
     name: Option<String>, // name=foo,name=bar
     #[param(required = false)]
+    image: Option<String>, // image=nginx:1.27,redis
+    #[param(required = false)]
     all: Option<bool>,
+    /// `true`: only containers Orqos created (`com.orqos.managed=true`);
+    /// `false`: only ones it didn't
+    #[param(required = false)]
+    managed: Option<bool>,
+    /// Max number of containers to return
+    #[param(required = false)]
+    limit: Option<usize>,
+    /// Number of containers to skip (after sorting)
+    #[param(required = false)]
+    offset: Option<usize>,
+    /// `created` (default), `name` or `status` (by state: created, running,
+    /// paused, restarting, exited, removing, dead)
+    #[param(required = false)]
+    sort: Option<String>,
+    /// `asc` or `desc`; defaults to `desc` for `created`, `asc` otherwise
+    #[param(required = false)]
+    order: Option<String>,
 }
 
 #[utoipa::path(
@@ -27,39 +50,52 @@ pub struct ContainerQuery {
     path = "/containers",
     params(ContainerQuery),
     responses(
-        (status = 200, body = Object)
+        (status = 200, body = Object, headers(
+            ("X-Total-Count" = usize, description = "Number of matching containers before pagination")
+        )),
+        (status = 400, description = "Invalid sort or order"),
     ),
     tag = "Containers",
 )]
 pub async fn list_containers_handler(
     State(app): State<Arc<AppState>>,
     Query(q): Query<ContainerQuery>,
-) -> Result<Json<Vec<ContainerSummary>>, impl IntoResponse> {
+) -> Result<impl IntoResponse, (StatusCode, String)> {
     use bollard::query_parameters::ListContainersOptionsBuilder as Lcob;
-    use std::collections::HashMap;
-
-    // Build Docker filter map dynamically
-    let mut filters: HashMap<&str, Vec<String>> = HashMap::new();
 
-    if let Some(ref v) = q.label {
-        let labels: Vec<String> = v.split(',').map(str::to_owned).collect();
-        if !labels.is_empty() {
-            filters.insert("label", labels);
-        }
+    let mut filters = build_filters(
+        q.label.as_deref(),
+        q.status.as_deref(),
+        q.name.as_deref(),
+        q.image.as_deref(),
+    );
+    // Docker can't filter on a missing label, so `managed=false` is applied
+    // to the listing below instead.
+    if q.managed == Some(true) {
+        filters
+            .entry("label")
+            .or_default()
+            .push(format!("{MANAGED_LABEL}=true"));
     }
-    if let Some(ref v) = q.status {
-        let statuses: Vec<String> = v.split(',').map(str::to_owned).collect();
-        if !statuses.is_empty() {
-            filters.insert("status", statuses);
-        }
+
+    let sort = q.sort.as_deref().unwrap_or("created");
+    if !matches!(sort, "created" | "name" | "status") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "sort must be one of created, name, status".into(),
+        ));
     }
-    if let Some(ref v) = q.name {
-        let names: Vec<String> = v.split(',').map(str::to_owned).collect();
-        if !names.is_empty() {
-            filters.insert("name", names);
+    let descending = match q.order.as_deref() {
+        None => sort == "created",
+        Some("asc") => false,
+        Some("desc") => true,
+        Some(_) => {
+            return Err((StatusCode::BAD_REQUEST, "order must be asc or desc".into()));
         }
-    }
+    };
 
+    // No daemon-side `limit`: X-Total-Count needs the full match set, and
+    // the (cached) full listing is cheap to slice here.
     let opts = Lcob::new()
         .all(q.all.unwrap_or(false))
         .filters(&filters)
@@ -67,9 +103,90 @@ pub async fn list_containers_handler(
 
     tracing::debug!(?opts, "Listing containers with options");
 
-    app.docker
-        .list_containers(Some(opts))
+    // Same query → same key, regardless of the order filters were inserted.
+    let cache_key = format!(
+        "all={}|{:?}",
+        q.all.unwrap_or(false),
+        filters.iter().collect::<std::collections::BTreeMap<_, _>>()
+    );
+
+    let list = app
+        .container_cache
+        .get_or_fetch(cache_key, || {
+            with_retry("list_containers", app.config.docker_retries, || {
+                app.docker.list_containers(Some(opts.clone()))
+            })
+        })
         .await
-        .map(Json)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut list: Vec<ContainerSummary> = list.as_ref().clone();
+    if q.managed == Some(false) {
+        list.retain(|c| !is_managed(c));
+    }
+    let total = list.len();
+
+    match sort {
+        "name" => list.sort_by(|a, b| first_name(a).cmp(first_name(b))),
+        // By lifecycle state; the free-text status only breaks ties
+        "status" => list.sort_by(|a, b| (a.state, &a.status).cmp(&(b.state, &b.status))),
+        _ => list.sort_by_key(|c| c.created),
+    }
+    if descending {
+        list.reverse();
+    }
+
+    let mut page: Vec<ContainerSummary> = list
+        .into_iter()
+        .skip(q.offset.unwrap_or(0))
+        .take(q.limit.unwrap_or(usize::MAX))
+        .collect();
+    for c in &mut page {
+        if let Some(labels) = &mut c.labels {
+            redact_labels(app.config.sensitive_keys.as_ref(), labels);
+        }
+    }
+
+    Ok(([("x-total-count", total.to_string())], Json(page)))
+}
+
+/// Docker `filters` for the comma-separated `label`, `status`, `name` and
+/// `image` query values (`image` maps to Docker's `ancestor`).
+pub(crate) fn build_filters(
+    label: Option<&str>,
+    status: Option<&str>,
+    name: Option<&str>,
+    image: Option<&str>,
+) -> HashMap<&'static str, Vec<String>> {
+    [
+        ("label", label),
+        ("status", status),
+        ("name", name),
+        ("ancestor", image),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| {
+        let values: Vec<String> = value?
+            .split(',')
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned)
+            .collect();
+        (!values.is_empty()).then_some((key, values))
+    })
+    .collect()
+}
+
+fn is_managed(c: &ContainerSummary) -> bool {
+    c.labels
+        .as_ref()
+        .and_then(|l| l.get(MANAGED_LABEL))
+        .is_some_and(|v| v == "true")
+}
+
+pub(crate) fn first_name(c: &ContainerSummary) -> &str {
+    c.names
+        .as_ref()
+        .and_then(|n| n.first())
+        .map(|n| n.as_str())
+        .unwrap_or("")
 }
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    routes::{
+        exec::{run_exec_once, ExecRequest, ExecResponse},
+        write_file::{path_exists, validate_write_path},
+    },
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeleteFileRequest {
+    /// **Absolute** path inside the target container
+    pub path: String,
+    /// Delete directories and their contents (`rm -rf`)
+    pub recursive: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/containers/{id}/delete-file",
+    request_body = DeleteFileRequest,
+    responses(
+        (status = 204, description = "File deleted"),
+        (status = 400, description = "Invalid request"),
+        (status = 404, description = "Path does not exist"),
+        (status = 500, description = "Internal error"),
+    ),
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    tag = "Containers"
+)]
+pub async fn delete_file_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container_id): Path<String>,
+    Json(req): Json<DeleteFileRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    validate_write_path(&req.path)?;
+    if req.path.trim_end_matches('/').is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "refusing to delete /".into()));
+    }
+
+    if !path_exists(&state, &container_id, &req.path).await? {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{} does not exist", req.path),
+        ));
+    }
+
+    let flags = if req.recursive.unwrap_or(false) {
+        "-rf"
+    } else {
+        "-f"
+    };
+
+    // Resolve `rm` through PATH first; images with a stripped PATH may still
+    // ship the binary at its usual location.
+    let mut result = run_rm(&state, &container_id, "rm", flags, &req.path).await;
+    if matches!(
+        &result,
+        Err(_)
+            | Ok(ExecResponse {
+                exit_code: 126 | 127,
+                ..
+            })
+    ) {
+        result = run_rm(&state, &container_id, "/bin/rm", flags, &req.path).await;
+    }
+
+    let resp = result.map_err(|(sc, msg)| (sc, format!("exec rm failed: {msg}")))?;
+    if resp.exit_code != 0 {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("rm exited with {}: {}", resp.exit_code, resp.stderr.trim()),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn run_rm(
+    state: &AppState,
+    container_id: &str,
+    rm: &str,
+    flags: &str,
+    path: &str,
+) -> Result<ExecResponse, (StatusCode, String)> {
+    let exec_req = ExecRequest {
+        cmd: vec![rm.into(), flags.into(), "--".into(), path.into()],
+        user: Some("root".into()),
+        ..Default::default()
+    };
+    run_exec_once(state, container_id, &exec_req).await
+}
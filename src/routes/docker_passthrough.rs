@@ -0,0 +1,269 @@
+//! Allowlisted passthrough to Docker API calls Orqos has no dedicated
+//! endpoint for. Each method name maps to exactly one bollard call; nothing
+//! is proxied to the socket as-is.
+//!
+//! Off unless `ORQOS_DOCKER_PASSTHROUGH` lists the methods to expose. Orqos
+//! has no auth of its own, so only enable this behind something that does.
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+};
+use bollard::{
+    errors::Error as BollardError,
+    query_parameters::{
+        InspectContainerOptions, InspectNetworkOptions, ListNetworksOptions, ListVolumesOptions,
+        PruneContainersOptions, PruneImagesOptions, PruneNetworksOptions, PruneVolumesOptions,
+        TopOptions,
+    },
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::{
+    audit_log::{identity, result_of, ChangeRecord},
+    redact::redact_config,
+    routes::{
+        container_create::MANAGED_LABEL, container_id::validate_container_id,
+        images::validate_image_ref,
+    },
+    state::AppState,
+};
+
+lazy_static! {
+    /// Network and volume names or IDs
+    static ref OBJECT_NAME_RE: Regex = Regex::new(r"^[a-zA-Z0-9][a-zA-Z0-9_.-]{0,127}$").unwrap();
+}
+
+/// Every method the passthrough knows; `ORQOS_DOCKER_PASSTHROUGH` picks
+/// which of these are actually reachable.
+pub const PASSTHROUGH_METHODS: &[&str] = &[
+    "info",
+    "version",
+    "df",
+    "inspect_container",
+    "top_processes",
+    "inspect_image",
+    "image_history",
+    "inspect_network",
+    "inspect_volume",
+    "list_networks",
+    "list_volumes",
+    "prune_containers",
+    "prune_images",
+    "prune_networks",
+    "prune_volumes",
+];
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PassthroughRequest {
+    /// Container, image, network or volume for the `inspect_*`,
+    /// `top_processes` and `image_history` methods
+    pub id: Option<String>,
+    /// Docker filters for the `list_*` and `prune_*` methods, e.g.
+    /// `{"until": ["24h"]}`
+    pub filters: Option<HashMap<String, Vec<String>>>,
+    /// For `prune_containers`, `prune_networks` and `prune_volumes`: only
+    /// prune what Orqos created (`com.orqos.managed=true`). Default `true`.
+    /// Images carry no such label, so `prune_images` ignores it. Orqos never
+    /// creates volumes, so `prune_volumes` removes nothing unless this is
+    /// `false`.
+    pub managed_only: Option<bool>,
+}
+
+/// Call one allowlisted Docker API method and return the daemon's response
+/// unchanged, except that container and image inspects get the same env and
+/// label redaction as every other endpoint.
+#[utoipa::path(
+    post,
+    path = "/docker/{method}",
+    request_body(content = PassthroughRequest, description = "Optional; `id` and/or `filters` depending on the method"),
+    params(
+        ("method" = String, Path, description = "One of `info`, `version`, `df`, `inspect_container`, `top_processes`, `inspect_image`, `image_history`, `inspect_network`, `inspect_volume`, `list_networks`, `list_volumes`, `prune_containers`, `prune_images`, `prune_networks`, `prune_volumes`; only those listed in `ORQOS_DOCKER_PASSTHROUGH` are enabled")
+    ),
+    responses(
+        (status = 200, description = "The Docker API response, as JSON"),
+        (status = 400, description = "Missing or invalid `id`"),
+        (status = 403, description = "Method not enabled in `ORQOS_DOCKER_PASSTHROUGH`"),
+        (status = 404, description = "Unknown method, or the object was not found"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Docker",
+)]
+pub async fn docker_passthrough_handler(
+    State(state): State<Arc<AppState>>,
+    Path(method): Path<String>,
+    headers: HeaderMap,
+    req: Option<Json<PassthroughRequest>>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    // Only the prunes change anything; the rest is left out of the audit log.
+    if !method.starts_with("prune_") {
+        return call(&state, &method, req).await;
+    }
+    let started = Instant::now();
+    let resource = req.as_ref().and_then(|Json(r)| r.id.clone());
+    let res = call(&state, &method, req).await;
+    let status = res.as_ref().map_or_else(|(s, _)| *s, |_| StatusCode::OK);
+    state.audit.change(&ChangeRecord {
+        actor: identity(&headers),
+        action: format!("POST /docker/{method}"),
+        resource,
+        result: result_of(status),
+        status: status.as_u16(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+    res
+}
+
+async fn call(
+    state: &AppState,
+    method: &str,
+    req: Option<Json<PassthroughRequest>>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    if !PASSTHROUGH_METHODS.contains(&method) {
+        return Err((StatusCode::NOT_FOUND, format!("unknown method {method}")));
+    }
+    if !state.config.docker_passthrough.iter().any(|m| m == method) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("method {method} is not enabled in ORQOS_DOCKER_PASSTHROUGH"),
+        ));
+    }
+
+    let Json(req) = req.unwrap_or_default();
+    let docker = &state.docker;
+    let sensitive = state.config.sensitive_keys.as_ref();
+    let filters = req.filters;
+    let prune_filters = if req.managed_only.unwrap_or(true) {
+        let mut filters = filters.clone().unwrap_or_default();
+        filters
+            .entry("label".into())
+            .or_default()
+            .push(format!("{MANAGED_LABEL}=true"));
+        Some(filters)
+    } else {
+        filters.clone()
+    };
+    let id = || {
+        req.id
+            .as_deref()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("{method} requires an id")))
+    };
+    let container =
+        || validate_container_id(id()?).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()));
+    // The id ends up in the daemon URL, so it mustn't carry `/`, `..` or `?`
+    let object = || {
+        let id = id()?;
+        if !OBJECT_NAME_RE.is_match(id) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("invalid network or volume {id:?}"),
+            ));
+        }
+        Ok(id)
+    };
+    let image = || {
+        let image = id()?;
+        validate_image_ref(image)?;
+        Ok::<_, (StatusCode, String)>(image)
+    };
+
+    let value = match method {
+        "info" => to_json(docker.info().await),
+        "version" => to_json(docker.version().await),
+        "df" => to_json(docker.df(None).await),
+        "inspect_container" => {
+            let mut res = docker
+                .inspect_container(container()?, None::<InspectContainerOptions>)
+                .await;
+            if let Ok(bollard::models::ContainerInspectResponse {
+                config: Some(cfg), ..
+            }) = &mut res
+            {
+                redact_config(sensitive, &mut cfg.env, &mut cfg.labels);
+            }
+            to_json(res)
+        }
+        "top_processes" => to_json(docker.top_processes(container()?, None::<TopOptions>).await),
+        "inspect_image" => {
+            let mut res = docker.inspect_image(image()?).await;
+            if let Ok(bollard::models::ImageInspect {
+                config: Some(cfg), ..
+            }) = &mut res
+            {
+                redact_config(sensitive, &mut cfg.env, &mut cfg.labels);
+            }
+            to_json(res)
+        }
+        "image_history" => to_json(docker.image_history(image()?).await),
+        "inspect_network" => to_json(
+            docker
+                .inspect_network(object()?, None::<InspectNetworkOptions>)
+                .await,
+        ),
+        "inspect_volume" => to_json(docker.inspect_volume(object()?).await),
+        "list_networks" => to_json(
+            docker
+                .list_networks(Some(ListNetworksOptions { filters }))
+                .await,
+        ),
+        "list_volumes" => to_json(
+            docker
+                .list_volumes(Some(ListVolumesOptions { filters }))
+                .await,
+        ),
+        "prune_containers" => to_json(
+            docker
+                .prune_containers(Some(PruneContainersOptions {
+                    filters: prune_filters,
+                }))
+                .await,
+        ),
+        "prune_images" => to_json(
+            docker
+                .prune_images(Some(PruneImagesOptions { filters }))
+                .await,
+        ),
+        "prune_networks" => to_json(
+            docker
+                .prune_networks(Some(PruneNetworksOptions {
+                    filters: prune_filters,
+                }))
+                .await,
+        ),
+        "prune_volumes" => to_json(
+            docker
+                .prune_volumes(Some(PruneVolumesOptions {
+                    filters: prune_filters,
+                }))
+                .await,
+        ),
+        _ => return Err((StatusCode::NOT_FOUND, format!("unknown method {method}"))),
+    }?;
+
+    tracing::info!(method, "docker passthrough call");
+    Ok(Json(value))
+}
+
+/// Serialize a bollard result, keeping the daemon's own status on errors.
+fn to_json<T: Serialize>(res: Result<T, BollardError>) -> Result<Value, (StatusCode, String)> {
+    match res {
+        Ok(v) => {
+            serde_json::to_value(v).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+        Err(BollardError::DockerResponseServerError {
+            status_code,
+            message,
+        }) => Err((
+            StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            message,
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
@@ -1,16 +1,35 @@
-use std::sync::Arc;
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
 use axum::{
-    extract::{ws::Message, State, WebSocketUpgrade},
+    extract::{ws::Message, Query, State, WebSocketUpgrade},
     response::IntoResponse,
 };
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
 
 use crate::state::AppState;
 
+/// Default number of idle seconds before a heartbeat frame is sent.
+const DEFAULT_HEARTBEAT_SECS: u64 = 30;
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsWsQuery {
+    /// Idle seconds between `{"type":"heartbeat"}` frames (0 disables, default 30)
+    #[param(required = false)]
+    heartbeat: Option<u64>,
+}
+
 #[utoipa::path(
     get,
     path = "/events/ws",
-    description = "Exposes Docker events via WS",
+    description = "Exposes Docker events via WS. When no event has been sent for `heartbeat` seconds, a synthetic `{\"type\":\"heartbeat\",\"ts\":<epoch ms>}` frame is sent instead. A client that falls behind the event buffer receives `{\"type\":\"lagged\",\"missed\":<n>}` and keeps streaming.",
+    params(EventsWsQuery),
     responses(
         (status = 101, description = "WebSocket upgrade initiated")
     ),
@@ -18,13 +37,47 @@ use crate::state::AppState;
 )]
 pub async fn events_ws(
     State(app): State<Arc<AppState>>,
+    Query(q): Query<EventsWsQuery>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
+    let heartbeat = match q.heartbeat.unwrap_or(DEFAULT_HEARTBEAT_SECS) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+
     ws.on_upgrade(move |mut socket| async move {
         let mut rx = app.events_tx.subscribe();
-        while let Ok(ev) = rx.recv().await {
-            // Ignore errors if client closed
-            let _ = socket.send(Message::Text(ev.to_string().into())).await;
+
+        let period = heartbeat.unwrap_or(Duration::from_secs(DEFAULT_HEARTBEAT_SECS));
+        let mut ticker = interval_at(Instant::now() + period, period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let msg = tokio::select! {
+                ev = rx.recv() => match ev {
+                    Ok(ev) => {
+                        // Real traffic counts as liveness; push the next heartbeat out.
+                        ticker.reset();
+                        ev.to_string()
+                    }
+                    // This client fell behind the ring buffer; tell it how much
+                    // it missed so it can resync instead of silently drifting.
+                    Err(RecvError::Lagged(missed)) => {
+                        app.events_dropped.fetch_add(missed, Ordering::Relaxed);
+                        json!({ "type": "lagged", "missed": missed }).to_string()
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick(), if heartbeat.is_some() => json!({
+                    "type": "heartbeat",
+                    "ts": chrono::Utc::now().timestamp_millis(),
+                })
+                .to_string(),
+            };
+
+            if socket.send(Message::Text(msg.into())).await.is_err() {
+                break; // client closed
+            }
         }
     })
 }
@@ -1,42 +1,52 @@
 //! Exec support for Orqos
 //! -----------------------------------------------------------
-//! * REST   POST /containers/:id/exec        → buffered stdout/stderr + exit‑code (JSON)
-//! * WS     GET  /containers/:id/exec/ws     → live stream of stdout/stderr frames
+//! * REST   POST /containers/{id}/exec             → buffered stdout/stderr + exit‑code (JSON),
+//!   or `{ exec_id }` when `detach: true`
+//! * REST   GET  /containers/{id}/exec/{exec_id}   → status of a (detached) exec
+//! * WS     GET  /containers/{id}/exec/ws          → live stream of stdout/stderr frames
+//!
+//! Every exec a client runs through these endpoints is recorded in the
+//! audit log (see [`crate::audit_log`]).
 //!
 //! All functions are async + Tokio‑friendly.  No Arc<Docker> is needed –
 //! `bollard::Docker` is internally Arc‑backed and `Clone`.
 //! -----------------------------------------------------------
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         Json, Path, Query, State,
     },
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use bollard::{
     container::LogOutput,
+    errors::Error as BollardError,
     exec::{CreateExecOptions, StartExecOptions, StartExecResults},
+    query_parameters::InspectContainerOptions,
     Docker,
 };
 use futures::SinkExt;
 use futures_util::StreamExt;
-use lazy_static::lazy_static;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tokio::io::AsyncWriteExt;
 use tracing::error;
 use utoipa::ToSchema;
 
+use crate::audit_log::{identity, ExecRecord};
+use crate::config::Config;
+use crate::routes::container_id::validate_container_id;
 use crate::state::AppState;
 
 // ---------------------------------------------------------------------------
 // JSON payloads
 // ---------------------------------------------------------------------------
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Default, Deserialize, ToSchema)]
 pub struct ExecRequest {
     #[schema(example = json!(["ls", "-la", "/data"]))]
     pub cmd: Vec<String>,
@@ -44,6 +54,43 @@ pub struct ExecRequest {
     #[serde(default)]
     #[schema(example = "1000:1000")]
     pub user: Option<String>,
+
+    /// Start the command in the background and return its `exec_id`
+    /// instead of waiting for output (REST only)
+    #[serde(default)]
+    pub detach: Option<bool>,
+
+    /// Fed to the command's stdin, which is then closed (REST only; not
+    /// with `detach`)
+    #[serde(default)]
+    #[schema(example = "key = value\n")]
+    pub stdin: Option<String>,
+
+    /// `utf8` (default) or `base64` for binary `stdin`
+    #[serde(default)]
+    pub stdin_encoding: Option<String>,
+}
+
+impl ExecRequest {
+    /// The decoded `stdin` bytes, if any.
+    fn stdin_bytes(&self) -> Result<Option<Vec<u8>>, (StatusCode, String)> {
+        let Some(stdin) = &self.stdin else {
+            return Ok(None);
+        };
+        match self.stdin_encoding.as_deref().unwrap_or("utf8") {
+            "utf8" => Ok(Some(stdin.as_bytes().to_vec())),
+            "base64" => BASE64.decode(stdin).map(Some).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid base64 stdin: {e}"),
+                )
+            }),
+            other => Err((
+                StatusCode::BAD_REQUEST,
+                format!("unsupported stdin_encoding {other:?} (expected utf8 or base64)"),
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -51,58 +98,263 @@ pub struct ExecResponse {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i64,
+    /// Output hit the capture limit; the command was detached from early
+    pub truncated: bool,
 }
 
-lazy_static! {
-    static ref CONTAINER_ID_RE: Regex = Regex::new(r"^[a-zA-Z0-9_.-]{1,64}$").unwrap();
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExecStartedResponse {
+    pub exec_id: String,
 }
 
-fn validate_container_id(id: &str) -> Result<(), &'static str> {
-    if CONTAINER_ID_RE.is_match(id) {
-        Ok(())
-    } else {
-        Err("Invalid container ID format")
-    }
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExecInspectResponse {
+    pub exec_id: String,
+    pub running: bool,
+    /// `None` while the command is still running
+    pub exit_code: Option<i64>,
+    pub pid: Option<i64>,
 }
 
 fn validate_command(cmd: &[String]) -> Result<(), &'static str> {
     if cmd.is_empty() {
         return Err("Command cannot be empty");
     }
-    // Add any other policy checks you need (black‑list, length, etc.)
     Ok(())
 }
 
+/// Apply the operator's `ORQOS_EXEC_ALLOW` / `ORQOS_EXEC_DENY` lists to the
+/// first token of `cmd`, compared by basename so `/bin/rm` counts as `rm`.
+///
+/// This is a guardrail for the public exec API only (a shell can still run
+/// anything it likes); Orqos' own internal execs are not subject to it.
+fn check_exec_policy(config: &Config, cmd: &[String]) -> Result<(), (StatusCode, String)> {
+    let Some(first) = cmd.first() else {
+        return Ok(());
+    };
+    let binary = first.rsplit('/').next().unwrap_or(first);
+
+    let listed = |list: &[String]| list.iter().any(|b| b == binary || b == first);
+    if listed(&config.exec_deny) || (!config.exec_allow.is_empty() && !listed(&config.exec_allow)) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("command {binary:?} is not allowed by exec policy"),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExecOnceQuery {
+    /// Respond with 422 (body unchanged) when the command exits non-zero
+    #[param(required = false)]
+    pub fail_on_nonzero: Option<bool>,
+}
+
 #[utoipa::path(
     post,
     path = "/containers/{id}/exec",
     request_body = ExecRequest,
     responses(
-        (status = 200, description = "Command executed successfully", body = ExecResponse),
+        (status = 200, description = "Command executed successfully", body = ExecResponse, headers(
+            ("X-Exit-Code" = i64, description = "Exit code of the command, mirroring the body")
+        )),
+        (status = 202, description = "Command started in the background (`detach: true`)", body = ExecStartedResponse),
+        (status = 400, description = "Invalid container ID, command or `stdin`, or `stdin` with `detach`"),
+        (status = 403, description = "Command forbidden by `ORQOS_EXEC_ALLOW`/`ORQOS_EXEC_DENY`"),
+        (status = 422, description = "Command exited non-zero (only with `fail_on_nonzero=true`)", body = ExecResponse, headers(
+            ("X-Exit-Code" = i64, description = "Exit code of the command, mirroring the body")
+        )),
         (status = 500, description = "Internal server error"),
     ),
     params(
         ("id" = String, Path, description = "ID or name of the container"),
+        ExecOnceQuery,
     ),
     tag = "Containers",
     operation_id = "exec_in_container",
     summary = "Execute a command in a running container",
-    description = "Creates a one-time `docker exec` session inside the specified container and returns the captured stdout/stderr output and exit code."
+    description = "Creates a one-time `docker exec` session inside the specified container and returns the captured stdout/stderr output and exit code. With `detach: true` the command is started in the background and its `exec_id` returned for polling via `GET /containers/{id}/exec/{exec_id}`. `stdin` (optionally base64) is written to the command's stdin, which is then closed."
 )]
 pub async fn exec_once_handler(
     State(state): State<Arc<AppState>>,
     Path(container): Path<String>,
+    Query(q): Query<ExecOnceQuery>,
+    headers: HeaderMap,
     Json(req): Json<ExecRequest>,
-) -> Result<Json<ExecResponse>, (StatusCode, String)> {
-    validate_container_id(&container).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+) -> Result<Response, (StatusCode, String)> {
+    let started = Instant::now();
+    let detach = req.detach.unwrap_or(false);
+    let transport = if detach { "rest-detached" } else { "rest" };
+    let record = || ExecRecord {
+        duration_ms: started.elapsed().as_millis() as u64,
+        ..ExecRecord::new(
+            &container,
+            &req.cmd,
+            req.user.as_deref(),
+            transport,
+            identity(&headers),
+        )
+    };
+    // Refused attempts are audited too; they matter most
+    let audit_err = |e: &(StatusCode, String)| {
+        state.audit.exec(&ExecRecord {
+            error: Some(e.1.clone()),
+            ..record()
+        })
+    };
+
+    let allowed = check_exec_policy(&state.config, &req.cmd).and_then(|()| {
+        if detach && req.stdin.is_some() {
+            Err((
+                StatusCode::BAD_REQUEST,
+                "stdin cannot be combined with detach".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    });
+    allowed.inspect_err(audit_err)?;
+
+    if detach {
+        let exec_id = start_exec_detached(&state, &container, &req)
+            .await
+            .inspect_err(audit_err)?;
+        state.audit.exec(&record());
+        return Ok((StatusCode::ACCEPTED, Json(ExecStartedResponse { exec_id })).into_response());
+    }
+
+    let resp = run_exec_once(&state, &container, &req)
+        .await
+        .inspect_err(audit_err)?;
+    state.audit.exec(&ExecRecord {
+        exit_code: Some(resp.exit_code),
+        stdout_bytes: resp.stdout.len(),
+        stderr_bytes: resp.stderr.len(),
+        truncated: resp.truncated,
+        ..record()
+    });
+
+    let status = if q.fail_on_nonzero.unwrap_or(false) && resp.exit_code != 0 {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+
+    let exit_code = resp.exit_code.to_string();
+    Ok((status, [("x-exit-code", exit_code)], Json(resp)).into_response())
+}
+
+/// Create and start `req` without attaching; returns the exec ID.
+async fn start_exec_detached(
+    state: &AppState,
+    container: &str,
+    req: &ExecRequest,
+) -> Result<String, (StatusCode, String)> {
+    let container =
+        validate_container_id(container).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    validate_command(&req.cmd).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let exec = state
+        .docker
+        .create_exec(
+            container,
+            CreateExecOptions {
+                cmd: Some(req.cmd.clone()),
+                user: req.user.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(map_docker_err)?;
+
+    state
+        .docker
+        .start_exec(
+            &exec.id,
+            Some(StartExecOptions {
+                detach: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .map_err(err_500)?;
+
+    Ok(exec.id)
+}
+
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/exec/{exec_id}",
+    responses(
+        (status = 200, description = "Exec status", body = ExecInspectResponse),
+        (status = 404, description = "Exec not found for this container"),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        ("exec_id" = String, Path, description = "ID returned by a detached exec"),
+    ),
+    tag = "Containers",
+    summary = "Inspect an exec session",
+)]
+pub async fn exec_inspect_handler(
+    State(state): State<Arc<AppState>>,
+    Path((container, exec_id)): Path<(String, String)>,
+) -> Result<Json<ExecInspectResponse>, (StatusCode, String)> {
+    let container = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_owned();
+
+    let inspect = state
+        .docker
+        .inspect_exec(&exec_id)
+        .await
+        .map_err(map_docker_err)?;
+
+    // `{id}` may be a name, so resolve it before comparing with the exec's owner.
+    let owner = state
+        .docker
+        .inspect_container(&container, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?
+        .id;
+    if owner.is_none() || owner != inspect.container_id {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "exec does not belong to this container".into(),
+        ));
+    }
+
+    Ok(Json(ExecInspectResponse {
+        exec_id,
+        running: inspect.running.unwrap_or(false),
+        exit_code: inspect.exit_code,
+        pid: inspect.pid,
+    }))
+}
+
+/// Run `req` to completion inside `container` and collect its output.
+///
+/// A non-zero exit code is *not* an error here; callers decide what it means.
+pub(crate) async fn run_exec_once(
+    state: &AppState,
+    container: &str,
+    req: &ExecRequest,
+) -> Result<ExecResponse, (StatusCode, String)> {
+    let container =
+        validate_container_id(container).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
     validate_command(&req.cmd).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let stdin = req.stdin_bytes()?;
 
     // 1. Create the exec instance
     let exec = state
         .docker
         .create_exec(
-            &container,
+            container,
             CreateExecOptions {
+                attach_stdin: Some(stdin.is_some()),
                 attach_stdout: Some(true),
                 attach_stderr: Some(true),
                 cmd: Some(req.cmd.clone()),
@@ -120,7 +372,18 @@ pub async fn exec_once_handler(
         .await
         .map_err(err_500)?
     {
-        StartExecResults::Attached { output, .. } => output,
+        StartExecResults::Attached { output, mut input } => {
+            // Feed stdin from its own task so a command that writes before
+            // reading everything can't deadlock against us; closing our end
+            // gives it EOF. A command that exits early just gets EPIPE.
+            if let Some(stdin) = stdin {
+                tokio::spawn(async move {
+                    let _ = input.write_all(&stdin).await;
+                    let _ = input.shutdown().await;
+                });
+            }
+            output
+        }
         StartExecResults::Detached => {
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -129,31 +392,46 @@ pub async fn exec_once_handler(
         }
     };
 
-    // 3. Drain the stream
+    // 3. Drain the stream, up to the per-stream capture limit
+    let limit = state.config.max_exec_output;
     let mut stdout = Vec::<u8>::new();
     let mut stderr = Vec::<u8>::new();
+    let mut truncated = false;
 
     while let Some(frame) = output.next().await {
-        match frame.map_err(err_500)? {
-            LogOutput::StdOut { message } => stdout.extend_from_slice(&message),
-            LogOutput::StdErr { message } => stderr.extend_from_slice(&message),
-            _ => {}
+        let (buf, message) = match frame.map_err(err_500)? {
+            LogOutput::StdOut { message } => (&mut stdout, message),
+            LogOutput::StdErr { message } => (&mut stderr, message),
+            _ => continue,
+        };
+
+        let room = limit.saturating_sub(buf.len());
+        if message.len() > room {
+            buf.extend_from_slice(&message[..room]);
+            truncated = true;
+            break;
         }
+        buf.extend_from_slice(&message);
     }
 
+    // Dropping the attached stream hangs up on the exec, so a runaway
+    // command gets EPIPE instead of streaming into the void.
+    drop(output);
+
     // 4. Inspect for exit code
     let inspect = state.docker.inspect_exec(&exec.id).await.map_err(err_500)?;
 
-    Ok(Json(ExecResponse {
+    Ok(ExecResponse {
         stdout: String::from_utf8_lossy(&stdout).into_owned(),
         stderr: String::from_utf8_lossy(&stderr).into_owned(),
         exit_code: inspect.exit_code.unwrap_or(-1),
-    }))
+        truncated,
+    })
 }
 
 /// WebSocket Exec Protocol:
 /// ------------------------
-/// When a client connects to `/containers/:id/exec/ws`, the server streams
+/// When a client connects to `/containers/{id}/exec/ws`, the server streams
 /// stdout and stderr output from the attached `docker exec` session as
 /// structured JSON text messages in the format:
 /// `{"stream": "stdout|stderr", "data": "<output>"}`
@@ -172,40 +450,87 @@ pub async fn exec_once_handler(
 ///     Text frame: "__exit_code:0"
 ///
 /// Note: The default exit code fallback is `-1` if Docker provides no value.
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/exec/ws",
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        ("cmd" = Vec<String>, Query, description = "Command and arguments to run"),
+        ("user" = Option<String>, Query, description = "User to run the command as, e.g. `1000:1000`"),
+    ),
+    responses(
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 400, description = "Invalid container ID or command"),
+        (status = 403, description = "Command forbidden by exec policy"),
+    ),
+    tag = "Streaming",
+    summary = "Stream a command's output over WebSocket",
+    description = "Streams `{\"stream\": \"stdout|stderr\", \"data\": ...}` text frames, then a final `__exit_code:<n>` frame before closing."
+)]
 pub async fn exec_ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
     Path(container): Path<String>,
     Query(req): Query<ExecRequest>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    // Validate container ID
-    if let Err(e) = validate_container_id(&container) {
-        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
-    }
+    let actor = identity(&headers);
 
-    // Validate command vector
-    if let Err(e) = validate_command(&req.cmd) {
-        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
-    }
+    // Validate container ID and command vector, then the exec policy
+    let checked = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+        .and_then(|id| {
+            validate_command(&req.cmd).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+            check_exec_policy(&state.config, &req.cmd)?;
+            Ok(id.to_owned())
+        });
+    let container = match checked {
+        Ok(id) => id,
+        Err(e) => {
+            // Refused attempts are audited too; they matter most
+            state.audit.exec(&ExecRecord {
+                error: Some(e.1.clone()),
+                ..ExecRecord::new(&container, &req.cmd, req.user.as_deref(), "ws", actor)
+            });
+            return e.into_response();
+        }
+    };
 
-    ws.on_upgrade(move |socket| stream_exec_over_ws(socket, state.docker.clone(), container, req))
+    ws.on_upgrade(move |socket| stream_exec_over_ws(socket, state, container, req, actor))
 }
 
 async fn stream_exec_over_ws(
-    mut socket: WebSocket,
-    docker: Docker,
+    socket: WebSocket,
+    state: Arc<AppState>,
     container: String,
     req: ExecRequest,
+    actor: Option<String>,
 ) {
+    let started = Instant::now();
+    let mut record = ExecRecord::new(&container, &req.cmd, req.user.as_deref(), "ws", actor);
+    record.error = forward_exec(socket, &state.docker, &mut record).await.err();
+    record.duration_ms = started.elapsed().as_millis() as u64;
+    state.audit.exec(&record);
+}
+
+/// Run the exec and forward its output to `socket`, tallying output sizes
+/// and the exit code into `record`.
+async fn forward_exec(
+    mut socket: WebSocket,
+    docker: &Docker,
+    record: &mut ExecRecord<'_>,
+) -> Result<(), String> {
+    let (container, cmd, user) = (record.container, record.cmd, record.user);
+
     // 1. create_exec
     let Ok(exec) = docker
         .create_exec(
-            &container,
+            container,
             CreateExecOptions {
                 attach_stdout: Some(true),
                 attach_stderr: Some(true),
-                cmd: Some(req.cmd.clone()),
-                user: req.user.clone(),
+                cmd: Some(cmd.to_vec()),
+                user: user.map(str::to_owned),
                 ..Default::default()
             },
         )
@@ -214,7 +539,7 @@ async fn stream_exec_over_ws(
         let _ = socket
             .send(json!({ "error": "cannot create exec" }).to_string().into())
             .await;
-        return;
+        return Err("cannot create exec".into());
     };
 
     // 2. start_exec (attached)
@@ -225,13 +550,15 @@ async fn stream_exec_over_ws(
         let _ = socket
             .send(Message::Text("error: cannot start exec".into()))
             .await;
-        return;
+        return Err("cannot start exec".into());
     };
 
     // 3. Forward frames
+    let mut result = Ok(());
     while let Some(frame) = output.next().await {
         match frame {
             Ok(LogOutput::StdOut { message }) => {
+                record.stdout_bytes += message.len();
                 let payload =
                     json!({ "stream": "stdout", "data": String::from_utf8_lossy(&message) });
                 if socket
@@ -243,6 +570,7 @@ async fn stream_exec_over_ws(
                 }
             }
             Ok(LogOutput::StdErr { message }) => {
+                record.stderr_bytes += message.len();
                 let payload =
                     json!({ "stream": "stderr", "data": String::from_utf8_lossy(&message) });
                 if socket
@@ -257,6 +585,7 @@ async fn stream_exec_over_ws(
                 let _ = socket
                     .send(Message::Text(format!("error: {e}").into()))
                     .await;
+                result = Err(e.to_string());
                 break;
             }
             _ => {}
@@ -265,6 +594,7 @@ async fn stream_exec_over_ws(
 
     // 4. Final exit code sentinel
     if let Ok(inspect) = docker.inspect_exec(&exec.id).await {
+        record.exit_code = inspect.exit_code;
         // Send final exit code as a specially formatted text message.
         // Clients should detect this and treat it as the end of stream.
         let msg = format!("__exit_code:{}", inspect.exit_code.unwrap_or(-1));
@@ -272,6 +602,7 @@ async fn stream_exec_over_ws(
     }
 
     let _ = socket.close().await;
+    result
 }
 
 // ---------------------------------------------------------------------------
@@ -281,3 +612,14 @@ fn err_500<E: std::fmt::Display>(err: E) -> (StatusCode, String) {
     error!("{err}");
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
 }
+
+/// Like [`err_500`], but lets a daemon 404 through as-is.
+pub(crate) fn map_docker_err(err: BollardError) -> (StatusCode, String) {
+    match err {
+        BollardError::DockerResponseServerError {
+            status_code: 404,
+            message,
+        } => (StatusCode::NOT_FOUND, message),
+        e => err_500(e),
+    }
+}
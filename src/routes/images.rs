@@ -0,0 +1,304 @@
+//! Image endpoints
+//! -----------------------------------------------------------
+//! * REST   POST /images/{id}/tag    → add a `repo:tag` reference
+//! * REST   POST /images/{id}/push   → push to a registry, progress as NDJSON
+//!
+//! Image names containing `/` must be URL-encoded in the path (`%2F`).
+//! -----------------------------------------------------------
+
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Json, Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use bollard::{
+    auth::DockerCredentials,
+    errors::Error as BollardError,
+    models::PushImageInfo,
+    query_parameters::{PushImageOptionsBuilder, TagImageOptionsBuilder},
+};
+use futures_util::{stream, StreamExt};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use crate::{
+    routes::container_commit::{validate_repo, validate_tag},
+    state::AppState,
+};
+
+lazy_static! {
+    static ref IMAGE_ID_RE: Regex = Regex::new(r"^(?:sha256:)?[a-f0-9]{12,64}$").unwrap();
+    static ref DIGEST_RE: Regex = Regex::new(r"^sha256:[a-f0-9]{64}$").unwrap();
+}
+
+/// The parts of a `repo[:tag][@digest]` image reference.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ImageRef<'a> {
+    pub repo: &'a str,
+    pub tag: Option<&'a str>,
+    pub digest: Option<&'a str>,
+}
+
+impl ImageRef<'_> {
+    /// What to pull: the digest if pinned, else the tag, else `latest`.
+    pub fn reference(&self) -> &str {
+        self.digest.or(self.tag).unwrap_or("latest")
+    }
+}
+
+pub(crate) fn split_image_ref(image: &str) -> ImageRef<'_> {
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image, None),
+    };
+    // A `:` after the last `/` separates the tag; before it, it's a port.
+    let (repo, tag) = match name.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => (repo, Some(tag)),
+        _ => (name, None),
+    };
+    ImageRef { repo, tag, digest }
+}
+
+/// Registry credentials, forwarded to the daemon as `X-Registry-Auth`.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RegistryAuth {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Identity token from a previous `docker login`, instead of a password
+    pub identitytoken: Option<String>,
+    /// Registry host, e.g. `registry.local:5000`
+    pub serveraddress: Option<String>,
+}
+
+impl From<RegistryAuth> for DockerCredentials {
+    fn from(a: RegistryAuth) -> Self {
+        DockerCredentials {
+            username: a.username,
+            password: a.password,
+            identitytoken: a.identitytoken,
+            serveraddress: a.serveraddress,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TagRequest {
+    /// Target repository, e.g. `registry.local:5000/team/app`
+    pub repo: String,
+    /// Defaults to `latest`
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PushRequest {
+    /// Tag to push; all tags of the repository when omitted
+    pub tag: Option<String>,
+    pub auth: Option<RegistryAuth>,
+}
+
+/// Accepts an image ID (`sha256:…` or a hex prefix) or
+/// `repo[:tag][@sha256:…]`.
+pub(crate) fn validate_image_ref(image: &str) -> Result<(), (StatusCode, String)> {
+    if IMAGE_ID_RE.is_match(image) {
+        return Ok(());
+    }
+    let parts = split_image_ref(image);
+    validate_repo(parts.repo)?;
+    if let Some(tag) = parts.tag {
+        validate_tag(tag)?;
+    }
+    match parts.digest {
+        Some(digest) if !DIGEST_RE.is_match(digest) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("invalid digest {digest:?}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Map an error from the registry/daemon onto a status code, keeping auth
+/// problems (401/403) apart from everything else.
+pub(crate) fn map_image_err(err: BollardError) -> (StatusCode, String) {
+    match err {
+        BollardError::DockerResponseServerError {
+            status_code,
+            message,
+        } => (
+            StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            message,
+        ),
+        // Registry failures arrive as in-band stream errors
+        BollardError::DockerStreamError { error } => {
+            let lower = error.to_lowercase();
+            let status = if lower.contains("unauthorized")
+                || lower.contains("authentication required")
+                || lower.contains("no basic auth credentials")
+            {
+                StatusCode::UNAUTHORIZED
+            } else if lower.contains("denied") || lower.contains("forbidden") {
+                StatusCode::FORBIDDEN
+            } else {
+                StatusCode::BAD_GATEWAY
+            };
+            (status, error)
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/images/{id}/tag",
+    request_body = TagRequest,
+    params(
+        ("id" = String, Path, description = "Image ID or name (URL-encode `/`)")
+    ),
+    responses(
+        (status = 201, description = "Image tagged"),
+        (status = 400, description = "Invalid image, repo or tag"),
+        (status = 404, description = "Image not found"),
+        (status = 409, description = "Conflicting tag"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Images",
+)]
+pub async fn tag_image_handler(
+    State(state): State<Arc<AppState>>,
+    Path(image): Path<String>,
+    Json(req): Json<TagRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    validate_image_ref(&image)?;
+    validate_repo(&req.repo)?;
+    let tag = req.tag.as_deref().unwrap_or("latest");
+    validate_tag(tag)?;
+
+    let opts = TagImageOptionsBuilder::new()
+        .repo(&req.repo)
+        .tag(tag)
+        .build();
+    state
+        .docker
+        .tag_image(&image, Some(opts))
+        .await
+        .map_err(map_image_err)?;
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Push an image, streaming the daemon's progress messages as NDJSON.
+///
+/// Registries reject bad credentials before any layer is uploaded, so the
+/// progress is held back until the first layer starts moving: auth (and
+/// other early) failures still get a proper status code. Errors after that
+/// point are reported as a final `{"error": …}` line.
+#[utoipa::path(
+    post,
+    path = "/images/{id}/push",
+    request_body = PushRequest,
+    params(
+        ("id" = String, Path, description = "Image name without tag (URL-encode `/`)")
+    ),
+    responses(
+        (status = 200, description = "Push progress, one JSON object per line", content_type = "application/x-ndjson", body = String),
+        (status = 400, description = "Invalid image or tag"),
+        (status = 401, description = "Registry rejected the credentials"),
+        (status = 403, description = "Registry denied access to the repository"),
+        (status = 404, description = "Image not found"),
+        (status = 502, description = "Registry unreachable or failed", body = String)
+    ),
+    tag = "Images",
+)]
+pub async fn push_image_handler(
+    State(state): State<Arc<AppState>>,
+    Path(image): Path<String>,
+    Json(req): Json<PushRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    validate_repo(&image)?;
+    let mut opts = PushImageOptionsBuilder::new();
+    if let Some(tag) = &req.tag {
+        validate_tag(tag)?;
+        opts = opts.tag(tag);
+    }
+
+    let mut progress = state
+        .docker
+        .push_image(&image, Some(opts.build()), req.auth.map(Into::into))
+        .boxed();
+
+    let mut head: Vec<PushImageInfo> = Vec::new();
+    while let Some(item) = progress.next().await {
+        let info = item.map_err(map_image_err)?;
+        let started = matches!(
+            info.status.as_deref(),
+            Some("Pushing" | "Pushed" | "Layer already exists")
+        );
+        head.push(info);
+        if started {
+            break;
+        }
+    }
+
+    let lines = stream::iter(head.into_iter().map(Ok))
+        .chain(progress)
+        .map(|item| {
+            let value = match item {
+                Ok(info) => serde_json::to_value(info).unwrap_or_default(),
+                Err(e) => json!({ "error": map_image_err(e).1 }),
+            };
+            Ok::<_, std::convert::Infallible>(Bytes::from(format!("{value}\n")))
+        });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_image_ref_handles_ports_tags_and_digests() {
+        let digest = format!("sha256:{}", "a".repeat(64));
+        let cases = [
+            ("nginx", "nginx", None, None),
+            ("nginx:1.27", "nginx", Some("1.27"), None),
+            (
+                "registry.local:5000/app",
+                "registry.local:5000/app",
+                None,
+                None,
+            ),
+            (
+                "registry.local:5000/app:v2",
+                "registry.local:5000/app",
+                Some("v2"),
+                None,
+            ),
+        ];
+        for (image, repo, tag, digest) in cases {
+            assert_eq!(
+                split_image_ref(image),
+                ImageRef { repo, tag, digest },
+                "{image}"
+            );
+        }
+
+        let pinned = format!("registry.local:5000/app:v2@{digest}");
+        let parts = split_image_ref(&pinned);
+        assert_eq!(parts.repo, "registry.local:5000/app");
+        assert_eq!(parts.tag, Some("v2"));
+        assert_eq!(parts.reference(), digest);
+        assert!(validate_image_ref(&pinned).is_ok());
+        assert!(validate_image_ref("app@sha256:abc").is_err());
+        assert_eq!(split_image_ref("app").reference(), "latest");
+    }
+}
@@ -0,0 +1,161 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use bollard::{
+    container::LogOutput,
+    errors::Error as BollardError,
+    query_parameters::{LogsOptionsBuilder, RemoveContainerOptions, WaitContainerOptions},
+    Docker,
+};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::routes::container_create::{prepare_container, start_prepared_with, ContainerCreate};
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct JobRequest {
+    #[serde(flatten)]
+    pub container: ContainerCreate,
+    /// Remove the container once its output has been collected, or if it
+    /// fails to start. Happens even if the client disconnects first.
+    #[serde(default)]
+    pub remove_on_exit: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobResponse {
+    pub id: String,
+    pub exit_code: i64,
+    pub stdout: String,
+    pub stderr: String,
+    /// Logs hit the capture limit and were cut short
+    pub truncated: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    request_body = JobRequest,
+    responses(
+        (status = 200, description = "Job ran to completion", body = JobResponse),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "Containers",
+    summary = "Run a container to completion and collect its output",
+    description = "Equivalent of `docker run [--rm]`: creates and starts the container, waits for it to exit, then returns its exit code and logs."
+)]
+pub async fn run_job_handler(
+    State(app): State<Arc<AppState>>,
+    Json(req): Json<JobRequest>,
+) -> Result<Json<JobResponse>, (StatusCode, String)> {
+    let remove = req.remove_on_exit;
+    let name = req.container.name.clone();
+    let prepared = prepare_container(&app, req.container)?;
+
+    let mut created = false;
+    let started = start_prepared_with(&app, prepared, |step| {
+        created |= step == "starting";
+    })
+    .await;
+    let info = match started {
+        Ok(info) => info,
+        Err(e) => {
+            // Created but failed to start: still ours to clean up
+            if remove && created {
+                remove_job_container(&app.docker, &name).await;
+            }
+            return Err(e);
+        }
+    };
+
+    // Detached from the request so a client that disconnects mid-wait
+    // doesn't leave the container behind.
+    let task = {
+        let app = app.clone();
+        let id = info.id.clone();
+        tokio::spawn(async move {
+            let docker: &Docker = &app.docker;
+            let exit_code = wait_for_exit(docker, &id).await;
+            let logs = collect_logs(docker, &id, app.config.max_exec_output).await;
+            if remove {
+                remove_job_container(docker, &id).await;
+            }
+            (exit_code, logs)
+        })
+    };
+    let (exit_code, logs) = task
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let exit_code = exit_code.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let (stdout, stderr, truncated) =
+        logs.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(JobResponse {
+        id: info.id,
+        exit_code,
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated,
+    }))
+}
+
+/// Force-remove a finished (or never started) job container.
+async fn remove_job_container(docker: &Docker, id: &str) {
+    let opts = RemoveContainerOptions {
+        force: true,
+        ..Default::default()
+    };
+    if let Err(e) = docker.remove_container(id, Some(opts)).await {
+        tracing::warn!("failed to remove job container {id}: {e}");
+    }
+}
+
+/// Block until `id` exits and return its exit code.
+async fn wait_for_exit(docker: &Docker, id: &str) -> Result<i64, BollardError> {
+    let mut stream = docker.wait_container(id, None::<WaitContainerOptions>);
+    match stream.next().await {
+        Some(Ok(r)) => Ok(r.status_code),
+        // bollard reports a non-zero exit as an error carrying the code
+        Some(Err(BollardError::DockerContainerWaitError { code, .. })) => Ok(code),
+        Some(Err(e)) => Err(e),
+        None => Ok(-1),
+    }
+}
+
+/// Drain the full (non-follow) logs of `id` into separate stdout/stderr
+/// buffers, each capped at `limit` bytes. The flag reports truncation.
+pub(crate) async fn collect_logs(
+    docker: &Docker,
+    id: &str,
+    limit: usize,
+) -> Result<(Vec<u8>, Vec<u8>, bool), BollardError> {
+    let opts = LogsOptionsBuilder::new().stdout(true).stderr(true).build();
+    let mut stream = docker.logs(id, Some(opts));
+
+    let mut stdout = Vec::<u8>::new();
+    let mut stderr = Vec::<u8>::new();
+    let mut truncated = false;
+
+    while let Some(frame) = stream.next().await {
+        let (buf, message) = match frame? {
+            LogOutput::StdOut { message } => (&mut stdout, message),
+            LogOutput::StdErr { message } => (&mut stderr, message),
+            _ => continue,
+        };
+
+        let room = limit.saturating_sub(buf.len());
+        if message.len() > room {
+            buf.extend_from_slice(&message[..room]);
+            truncated = true;
+            break;
+        }
+        buf.extend_from_slice(&message);
+    }
+
+    Ok((stdout, stderr, truncated))
+}
@@ -0,0 +1,121 @@
+use std::{path::Component, sync::Arc};
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tar::EntryType;
+use utoipa::ToSchema;
+
+use crate::{
+    routes::read_file::{download_tar, open_archive, resolve_readable_path},
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ListDirRequest {
+    /// Absolute path of a directory inside the container
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirEntry {
+    pub name: String,
+    /// `file`, `dir`, `symlink` or `other`
+    pub kind: &'static str,
+    pub size: u64,
+    /// Permission bits in octal, e.g. `"0644"`
+    pub mode: String,
+}
+
+/// List the immediate children of a directory.
+///
+/// The directory is fetched as a tar archive and only the entry headers are
+/// inspected, so this works in images without a shell or `ls`.
+#[utoipa::path(
+    post,
+    path = "/containers/{id}/list-dir",
+    request_body = ListDirRequest,
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    responses(
+        (status = 200, description = "Directory entries", body = Vec<DirEntry>),
+        (status = 400, description = "Invalid path or not a directory"),
+        (status = 403, description = "Path outside the allowed base"),
+        (status = 404, description = "Directory not found"),
+        (status = 413, description = "Directory tree (with file contents) exceeds `ORQOS_MAX_READ_BYTES`"),
+        (status = 500, description = "Docker or server error", body = String)
+    ),
+    tag = "Containers",
+)]
+pub async fn list_dir_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Json(req): Json<ListDirRequest>,
+) -> Result<Json<Vec<DirEntry>>, (StatusCode, String)> {
+    let path = resolve_readable_path(&req.path)?;
+
+    // The daemon sends the whole tree, contents included
+    let tar_bytes = download_tar(
+        &state.docker,
+        &container,
+        &path,
+        state.config.max_read_bytes,
+    )
+    .await?;
+    let mut archive = open_archive(tar_bytes);
+
+    let entries = archive
+        .entries()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut out = Vec::new();
+    let mut saw_root = false;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        let header = entry.header();
+        let entry_path = entry
+            .path()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        // Archive paths are `<dirname>/<child>/...`; keep depth-1 children only.
+        let parts: Vec<_> = entry_path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(p) => Some(p.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        match parts.len() {
+            1 => {
+                if header.entry_type() != EntryType::Directory {
+                    return Err((StatusCode::BAD_REQUEST, "path is not a directory".into()));
+                }
+                saw_root = true;
+            }
+            2 => out.push(DirEntry {
+                name: parts[1].clone(),
+                kind: match header.entry_type() {
+                    EntryType::Regular | EntryType::Continuous => "file",
+                    EntryType::Directory => "dir",
+                    EntryType::Symlink => "symlink",
+                    _ => "other",
+                },
+                size: header.size().unwrap_or(0),
+                mode: format!("{:04o}", header.mode().unwrap_or(0) & 0o7777),
+            }),
+            _ => {}
+        }
+    }
+
+    if !saw_root && out.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "Directory not found".into()));
+    }
+
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Json(out))
+}
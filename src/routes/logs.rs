@@ -0,0 +1,925 @@
+//! Container logs
+//! -----------------------------------------------------------
+//! * REST   GET /containers/{id}/logs      → buffered lines (JSON)
+//! * WS     GET /containers/{id}/logs/ws   → follow, one frame per line
+//! * REST   GET /containers/{id}/logs/download → whole log as a file
+//! * WS     GET /logs/ws?containers=a,b    → follow several, tagged per line
+//!
+//! The first two accept `tail`, `since` (or `since_restart`), `until` and a
+//! server-side `grep` regex that is matched against each decoded line (live
+//! lines included for WS).
+//! -----------------------------------------------------------
+
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Json, Path, Query, State,
+    },
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use bollard::{
+    container::LogOutput,
+    errors::Error as BollardError,
+    query_parameters::{InspectContainerOptions, LogsOptions, LogsOptionsBuilder},
+};
+use chrono::DateTime;
+use flate2::{write::GzEncoder, Compression};
+use futures::{SinkExt, Stream};
+use futures_util::StreamExt;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{sync::mpsc, task::JoinSet};
+use utoipa::ToSchema;
+
+use crate::{
+    routes::{
+        container_export::attachment_name, container_id::validate_container_id,
+        exec::map_docker_err,
+    },
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogsQuery {
+    /// Number of lines from the end of the log, or `all` (default)
+    #[param(required = false)]
+    tail: Option<String>,
+    /// Only lines written after this UNIX timestamp (seconds)
+    #[param(required = false)]
+    since: Option<i32>,
+    /// Only lines written before this UNIX timestamp (seconds)
+    #[param(required = false)]
+    until: Option<i32>,
+    /// Keep only lines matching this regular expression
+    #[param(required = false)]
+    grep: Option<String>,
+    /// Include each line's RFC 3339 timestamp: as `ts` in the REST
+    /// response, as a line prefix over WebSocket
+    #[param(required = false)]
+    timestamps: Option<bool>,
+    /// Only lines since the container last started (`State.StartedAt`);
+    /// combined with `since`, the later of the two wins
+    #[param(required = false)]
+    since_restart: Option<bool>,
+    /// Remove ANSI escape sequences (colours, cursor moves) from each line
+    #[param(required = false)]
+    strip_ansi: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogLine {
+    /// RFC 3339 time the line was written, split off the front of `line`
+    /// (REST with `timestamps=true` only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<String>,
+    /// `stdout` or `stderr`
+    pub stream: &'static str,
+    pub line: String,
+}
+
+impl LogLine {
+    /// Move Docker's `<RFC 3339> ` prefix into `ts`. A prefix that doesn't
+    /// parse is left in place, so the raw line is never lost.
+    fn split_timestamp(mut self) -> Self {
+        if let Some((ts, rest)) = self.line.split_once(' ') {
+            if DateTime::parse_from_rfc3339(ts).is_ok() {
+                self.ts = Some(ts.to_owned());
+                self.line = rest.to_owned();
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogsResponse {
+    pub lines: Vec<LogLine>,
+    /// `true` if the output hit `ORQOS_MAX_EXEC_OUTPUT`; later lines are dropped
+    pub truncated: bool,
+}
+
+impl LogsQuery {
+    async fn options(
+        &self,
+        state: &AppState,
+        container: &str,
+        follow: bool,
+    ) -> Result<LogsOptions, (StatusCode, String)> {
+        let tail = parse_tail(self.tail.as_deref())?;
+        let mut opts = LogsOptionsBuilder::new()
+            .follow(follow)
+            .stdout(true)
+            .stderr(true)
+            .timestamps(self.timestamps.unwrap_or(false))
+            .tail(tail);
+        let restarted = if self.since_restart.unwrap_or(false) {
+            last_started(state, container).await?
+        } else {
+            None
+        };
+        if let Some(since) = self.since.max(restarted) {
+            opts = opts.since(since);
+        }
+        if let Some(until) = self.until {
+            opts = opts.until(until);
+        }
+        Ok(opts.build())
+    }
+
+    fn filter(&self) -> Result<Option<Regex>, (StatusCode, String)> {
+        self.grep
+            .as_deref()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid grep: {e}")))
+            })
+            .transpose()
+    }
+}
+
+fn parse_tail(tail: Option<&str>) -> Result<&str, (StatusCode, String)> {
+    let tail = tail.unwrap_or("all");
+    if tail != "all" && tail.parse::<u64>().is_err() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "tail must be a number or `all`".into(),
+        ));
+    }
+    Ok(tail)
+}
+
+/// When `container` last started, as a UNIX timestamp. `None` if it never
+/// ran or the daemon's value doesn't parse, so callers fall back to all logs.
+async fn last_started(
+    state: &AppState,
+    container: &str,
+) -> Result<Option<i32>, (StatusCode, String)> {
+    let info = state
+        .docker
+        .inspect_container(container, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?;
+
+    Ok(info
+        .state
+        .and_then(|s| s.started_at)
+        .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
+        .map(|ts| ts.timestamp())
+        // Never-started containers report the zero time (year 1)
+        .filter(|&secs| secs > 0)
+        .and_then(|secs| i32::try_from(secs).ok()))
+}
+
+/// Longest line [`LineSplitter`] holds back waiting for a newline; output
+/// that never has one (progress bars, binary) is emitted in pieces this big.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Reassembles frames into whole lines. TTY output and long lines can be
+/// split across frames, so partial lines are held per stream until their
+/// newline arrives (or they reach [`MAX_LINE_BYTES`]).
+#[derive(Default)]
+struct LineSplitter {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    strip_ansi: bool,
+}
+
+impl LineSplitter {
+    fn new(strip_ansi: bool) -> Self {
+        Self {
+            strip_ansi,
+            ..Self::default()
+        }
+    }
+
+    fn push(&mut self, frame: LogOutput) -> Vec<LogLine> {
+        let (stream, buf, message) = match frame {
+            LogOutput::StdOut { message } | LogOutput::Console { message } => {
+                ("stdout", &mut self.stdout, message)
+            }
+            LogOutput::StdErr { message } => ("stderr", &mut self.stderr, message),
+            _ => return Vec::new(),
+        };
+        buf.extend_from_slice(&message);
+
+        let mut lines = Vec::new();
+        loop {
+            let end = match buf.iter().position(|&b| b == b'\n') {
+                Some(pos) if pos < MAX_LINE_BYTES => pos + 1,
+                _ if buf.len() >= MAX_LINE_BYTES => utf8_boundary(buf, MAX_LINE_BYTES),
+                _ => break,
+            };
+            let raw: Vec<u8> = buf.drain(..end).collect();
+            lines.push(LogLine {
+                ts: None,
+                stream,
+                line: decode_line(&raw, self.strip_ansi),
+            });
+        }
+        lines
+    }
+
+    /// Whatever is left over without a trailing newline.
+    fn flush(&mut self) -> Vec<LogLine> {
+        let strip_ansi = self.strip_ansi;
+        [("stdout", &mut self.stdout), ("stderr", &mut self.stderr)]
+            .into_iter()
+            .filter(|(_, buf)| !buf.is_empty())
+            .map(|(stream, buf)| LogLine {
+                ts: None,
+                stream,
+                line: decode_line(&std::mem::take(buf), strip_ansi),
+            })
+            .collect()
+    }
+}
+
+/// `at`, moved back to the start of the UTF-8 character it falls in, so a
+/// forced split doesn't cut one in half.
+fn utf8_boundary(buf: &[u8], at: usize) -> usize {
+    let is_continuation = |i: usize| buf.get(i).is_some_and(|&b| b & 0xC0 == 0x80);
+    (at.saturating_sub(3)..=at)
+        .rev()
+        .find(|&i| !is_continuation(i))
+        .filter(|&i| i > 0)
+        .unwrap_or(at)
+}
+
+fn decode_line(raw: &[u8], strip: bool) -> String {
+    let s = String::from_utf8_lossy(raw);
+    let s = s.trim_end_matches(['\n', '\r']);
+    if strip {
+        strip_ansi(s)
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Drop ANSI escape sequences: CSI (`ESC [ … final`), OSC (`ESC ] … BEL`
+/// or `ESC \`) and the short `ESC [intermediates] final` forms such as
+/// charset selection. A sequence cut off at the end of `s` is dropped too.
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut state = AnsiState::Text;
+    for c in s.chars() {
+        state = state.next(c, &mut out);
+    }
+    out
+}
+
+#[derive(Clone, Copy, Default)]
+enum AnsiState {
+    #[default]
+    Text,
+    Esc,
+    EscIntermediate,
+    Csi,
+    Osc,
+    OscEsc,
+}
+
+impl AnsiState {
+    /// Consume `c`, appending it to `out` if it's outside a sequence.
+    fn next(self, c: char, out: &mut String) -> Self {
+        use AnsiState as State;
+        match (self, c) {
+            (State::Text, '\x1b') => State::Esc,
+            (State::Text, c) => {
+                out.push(c);
+                State::Text
+            }
+            (State::Esc, '[') => State::Csi,
+            (State::Esc, ']') => State::Osc,
+            (State::Esc, '\x20'..='\x2f') => State::EscIntermediate,
+            (State::Esc, _) => State::Text,
+            (State::EscIntermediate, '\x20'..='\x2f') => State::EscIntermediate,
+            (State::EscIntermediate, _) => State::Text,
+            // Parameter and intermediate bytes, until a final byte @..~
+            (State::Csi, '\x40'..='\x7e') => State::Text,
+            (State::Csi, _) => State::Csi,
+            (State::Osc, '\x07') => State::Text,
+            (State::Osc, '\x1b') => State::OscEsc,
+            (State::Osc, _) => State::Osc,
+            (State::OscEsc, '\\') => State::Text,
+            (State::OscEsc, _) => State::Osc,
+        }
+    }
+}
+
+/// [`strip_ansi`] over a byte stream cut at arbitrary points: the sequence
+/// state and an incomplete trailing UTF-8 character carry over to the next
+/// chunk.
+#[derive(Default)]
+struct AnsiStripper {
+    state: AnsiState,
+    partial: Vec<u8>,
+}
+
+impl AnsiStripper {
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.partial.extend_from_slice(bytes);
+        let tail = self
+            .partial
+            .split_off(self.partial.len() - incomplete_utf8_tail(&self.partial));
+        let text = std::mem::replace(&mut self.partial, tail);
+        let mut out = String::with_capacity(text.len());
+        for c in String::from_utf8_lossy(&text).chars() {
+            self.state = self.state.next(c, &mut out);
+        }
+        out
+    }
+
+    /// The held-back bytes of a character the stream ended in the middle of.
+    fn finish(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned()
+    }
+}
+
+/// How many bytes at the end of `buf` are the start of a UTF-8 character
+/// whose remaining bytes haven't arrived yet.
+fn incomplete_utf8_tail(buf: &[u8]) -> usize {
+    for back in 1..=buf.len().min(3) {
+        let b = buf[buf.len() - back];
+        if b & 0xC0 == 0x80 {
+            continue;
+        }
+        let len = match b {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if len > back { back } else { 0 };
+    }
+    0
+}
+
+fn keep(filter: &Option<Regex>, line: &LogLine) -> bool {
+    filter.as_ref().is_none_or(|re| re.is_match(&line.line))
+}
+
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/logs",
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        LogsQuery,
+    ),
+    responses(
+        (status = 200, description = "Log lines, oldest first", body = LogsResponse),
+        (status = 400, description = "Invalid container ID, tail or grep pattern"),
+        (status = 404, description = "Container not found"),
+        (status = 500, description = "Docker or server error", body = String),
+    ),
+    tag = "Containers",
+)]
+pub async fn logs_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<LogsQuery>,
+) -> Result<Json<LogsResponse>, (StatusCode, String)> {
+    let container = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_owned();
+    let opts = q.options(&state, &container, false).await?;
+    let filter = q.filter()?;
+    let limit = state.config.max_exec_output;
+
+    let mut stream = state.docker.logs(&container, Some(opts));
+    let mut splitter = LineSplitter::new(q.strip_ansi.unwrap_or(false));
+    let mut lines = Vec::new();
+    let mut size = 0usize;
+    let mut truncated = false;
+
+    let timestamps = q.timestamps.unwrap_or(false);
+    let mut accept = |line: LogLine, lines: &mut Vec<LogLine>| -> bool {
+        let line = if timestamps {
+            line.split_timestamp()
+        } else {
+            line
+        };
+        if !keep(&filter, &line) {
+            return true;
+        }
+        size += line.line.len();
+        if size > limit {
+            truncated = true;
+            return false;
+        }
+        lines.push(line);
+        true
+    };
+
+    'frames: while let Some(frame) = stream.next().await {
+        for line in splitter.push(frame.map_err(map_docker_err)?) {
+            if !accept(line, &mut lines) {
+                break 'frames;
+            }
+        }
+    }
+    for line in splitter.flush() {
+        if !accept(line, &mut lines) {
+            break;
+        }
+    }
+
+    Ok(Json(LogsResponse { lines, truncated }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/logs/ws",
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        LogsQuery,
+    ),
+    responses(
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 400, description = "Invalid container ID, tail or grep pattern"),
+        (status = 404, description = "Container not found (with `since_restart`)"),
+    ),
+    tag = "Streaming",
+    summary = "Follow a container's logs over WebSocket",
+    description = "Streams `{\"stream\": \"stdout|stderr\", \"line\": ...}` text frames, one per line (after `grep` filtering), until the container stops or the client disconnects."
+)]
+pub async fn logs_ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<LogsQuery>,
+) -> impl IntoResponse {
+    let container = match validate_container_id(&container) {
+        Ok(id) => id.to_owned(),
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    let (opts, filter) = match (q.options(&state, &container, true).await, q.filter()) {
+        (Ok(opts), Ok(filter)) => (opts, filter),
+        (Err(e), _) | (_, Err(e)) => return e.into_response(),
+    };
+
+    let splitter = LineSplitter::new(q.strip_ansi.unwrap_or(false));
+    ws.on_upgrade(move |socket| {
+        follow_logs_over_ws(socket, state, container, opts, filter, splitter)
+    })
+}
+
+async fn follow_logs_over_ws(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    container: String,
+    opts: LogsOptions,
+    filter: Option<Regex>,
+    mut splitter: LineSplitter,
+) {
+    let mut stream = state.docker.logs(&container, Some(opts));
+
+    loop {
+        // Watch the socket too: a quiet container (or a filter that drops
+        // everything) would otherwise never notice the client leaving.
+        let frame = tokio::select! {
+            frame = stream.next() => frame,
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let Some(frame) = frame else { break };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                let _ = socket
+                    .send(json!({ "error": e.to_string() }).to_string().into())
+                    .await;
+                break;
+            }
+        };
+        for line in splitter.push(frame) {
+            if !keep(&filter, &line) {
+                continue;
+            }
+            let payload = json!({ "stream": line.stream, "line": line.line });
+            if socket
+                .send(Message::Text(payload.to_string().into()))
+                .await
+                .is_err()
+            {
+                return; // client went away
+            }
+        }
+    }
+
+    for line in splitter.flush() {
+        if keep(&filter, &line) {
+            let payload = json!({ "stream": line.stream, "line": line.line });
+            let _ = socket.send(Message::Text(payload.to_string().into())).await;
+        }
+    }
+
+    let _ = socket.close().await;
+}
+
+/// Most containers one `/logs/ws` socket may follow.
+const MAX_MULTI_LOGS: usize = 16;
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MultiLogsQuery {
+    /// Comma-separated container IDs or names (at most 16)
+    containers: String,
+    /// Number of lines from the end of each log, or `all` (default)
+    #[param(required = false)]
+    tail: Option<String>,
+    /// Prefix each line with its RFC 3339 timestamp
+    #[param(required = false)]
+    timestamps: Option<bool>,
+    /// Remove ANSI escape sequences (colours, cursor moves) from each line
+    #[param(required = false)]
+    strip_ansi: Option<bool>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/logs/ws",
+    params(MultiLogsQuery),
+    responses(
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 400, description = "No, too many or invalid container IDs, or invalid tail"),
+        (status = 404, description = "One of the containers was not found"),
+    ),
+    tag = "Streaming",
+    summary = "Follow the logs of several containers over one WebSocket",
+    description = "Streams `{\"container\": ..., \"stream\": \"stdout|stderr\", \"data\": ...}` text frames, one per line, tagged with the container as given in `containers`. When one container's log ends (e.g. it stopped) a `{\"container\": ..., \"ended\": true}` frame is sent and the others carry on; the socket closes once all have ended or the client disconnects."
+)]
+pub async fn multi_logs_ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<MultiLogsQuery>,
+) -> impl IntoResponse {
+    let mut containers: Vec<String> = Vec::new();
+    for raw in q
+        .containers
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        match validate_container_id(raw) {
+            Ok(id) if !containers.iter().any(|c| c == id) => containers.push(id.to_owned()),
+            Ok(_) => {}
+            Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        }
+    }
+    if containers.is_empty() || containers.len() > MAX_MULTI_LOGS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("containers must list 1 to {MAX_MULTI_LOGS} containers"),
+        )
+            .into_response();
+    }
+    let tail = match parse_tail(q.tail.as_deref()) {
+        Ok(tail) => tail,
+        Err(e) => return e.into_response(),
+    };
+
+    // Check up front so a typo is a 404 rather than a stream that only ever
+    // reports one error frame
+    for container in &containers {
+        if let Err(e) = state
+            .docker
+            .inspect_container(container, None::<InspectContainerOptions>)
+            .await
+        {
+            return map_docker_err(e).into_response();
+        }
+    }
+
+    let opts = LogsOptionsBuilder::new()
+        .follow(true)
+        .stdout(true)
+        .stderr(true)
+        .timestamps(q.timestamps.unwrap_or(false))
+        .tail(tail)
+        .build();
+    let strip_ansi = q.strip_ansi.unwrap_or(false);
+    ws.on_upgrade(move |socket| follow_many_over_ws(socket, state, containers, opts, strip_ansi))
+}
+
+async fn follow_many_over_ws(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    containers: Vec<String>,
+    opts: LogsOptions,
+    strip_ansi: bool,
+) {
+    let (tx, mut rx) = mpsc::channel::<serde_json::Value>(256);
+    // Dropping the set aborts whatever is still following
+    let mut tasks = JoinSet::new();
+    for container in containers {
+        tasks.spawn(follow_one(
+            state.clone(),
+            container,
+            opts.clone(),
+            LineSplitter::new(strip_ansi),
+            tx.clone(),
+        ));
+    }
+    drop(tx);
+
+    loop {
+        tokio::select! {
+            frame = rx.recv() => {
+                let Some(frame) = frame else {
+                    break; // every log has ended
+                };
+                if socket.send(Message::Text(frame.to_string().into())).await.is_err() {
+                    return; // client went away
+                }
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    let _ = socket.close().await;
+}
+
+/// Forward one container's log lines to `tx`, then an `ended` marker.
+async fn follow_one(
+    state: Arc<AppState>,
+    container: String,
+    opts: LogsOptions,
+    mut splitter: LineSplitter,
+    tx: mpsc::Sender<serde_json::Value>,
+) {
+    let mut stream = state.docker.logs(&container, Some(opts));
+
+    while let Some(frame) = stream.next().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                let _ = tx
+                    .send(json!({ "container": container, "error": e.to_string() }))
+                    .await;
+                break;
+            }
+        };
+        for line in splitter.push(frame) {
+            let payload =
+                json!({ "container": container, "stream": line.stream, "data": line.line });
+            if tx.send(payload).await.is_err() {
+                return; // socket closed
+            }
+        }
+    }
+    for line in splitter.flush() {
+        let payload = json!({ "container": container, "stream": line.stream, "data": line.line });
+        let _ = tx.send(payload).await;
+    }
+
+    let _ = tx
+        .send(json!({ "container": container, "ended": true }))
+        .await;
+}
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogsDownloadQuery {
+    /// Gzip the file (`<name>.log.gz`)
+    #[param(required = false)]
+    gzip: Option<bool>,
+    /// Prefix each line with its RFC 3339 timestamp
+    #[param(required = false)]
+    timestamps: Option<bool>,
+    /// Remove ANSI escape sequences (colours, cursor moves) from each line
+    #[param(required = false)]
+    strip_ansi: Option<bool>,
+}
+
+/// Save a container's whole log (stdout and stderr, interleaved as written)
+/// as a file. The log is streamed from the daemon, never buffered.
+#[utoipa::path(
+    get,
+    path = "/containers/{id}/logs/download",
+    params(
+        ("id" = String, Path, description = "ID or name of the container"),
+        LogsDownloadQuery,
+    ),
+    responses(
+        (status = 200, description = "Log file (`text/plain`, or `application/gzip` with `gzip=true`)", content_type = "text/plain", body = String),
+        (status = 400, description = "Invalid container ID"),
+        (status = 404, description = "Container not found"),
+        (status = 500, description = "Docker or server error", body = String),
+    ),
+    tag = "Containers",
+)]
+pub async fn logs_download_handler(
+    State(state): State<Arc<AppState>>,
+    Path(container): Path<String>,
+    Query(q): Query<LogsDownloadQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let container = validate_container_id(&container)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_owned();
+
+    // Inspect first so a missing container is a 404, not a truncated 200
+    let info = state
+        .docker
+        .inspect_container(&container, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?;
+    let name = attachment_name(&info, &container);
+
+    let opts = LogsOptionsBuilder::new()
+        .stdout(true)
+        .stderr(true)
+        .timestamps(q.timestamps.unwrap_or(false))
+        .build();
+    let logs = state.docker.logs(&container, Some(opts));
+    let raw = if q.strip_ansi.unwrap_or(false) {
+        strip_ansi_stream(logs).boxed()
+    } else {
+        logs.map(|frame| Ok(frame.map_err(io::Error::other)?.into_bytes()))
+            .boxed()
+    };
+
+    let (content_type, filename, body) = if q.gzip.unwrap_or(false) {
+        (
+            "application/gzip",
+            format!("{name}.log.gz"),
+            Body::from_stream(gzip_stream(raw)),
+        )
+    } else {
+        (
+            "text/plain; charset=utf-8",
+            format!("{name}.log"),
+            Body::from_stream(raw),
+        )
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        body,
+    ))
+}
+
+/// Raw log frames with ANSI escapes removed. stdout and stderr frames
+/// interleave, so each stream keeps its own [`AnsiStripper`].
+fn strip_ansi_stream<S>(inner: S) -> impl Stream<Item = io::Result<Bytes>>
+where
+    S: Stream<Item = Result<LogOutput, BollardError>> + Unpin,
+{
+    let strippers = Some((AnsiStripper::default(), AnsiStripper::default()));
+    futures::stream::unfold((inner, strippers), |(mut inner, strippers)| async move {
+        let (mut out, mut err) = strippers?;
+        let text = match inner.next().await {
+            Some(Ok(LogOutput::StdErr { message })) => err.push(&message),
+            Some(Ok(frame)) => out.push(&frame.into_bytes()),
+            Some(Err(e)) => return Some((Err(io::Error::other(e)), (inner, None))),
+            None => {
+                let tail = out.finish() + &err.finish();
+                return Some((Ok(Bytes::from(tail)), (inner, None)));
+            }
+        };
+        Some((Ok(Bytes::from(text)), (inner, Some((out, err)))))
+    })
+}
+
+/// Gzip `inner` on the fly, yielding compressed bytes as they become
+/// available and the gzip trailer once `inner` ends.
+fn gzip_stream<S>(inner: S) -> impl Stream<Item = io::Result<Bytes>>
+where
+    S: Stream<Item = io::Result<Bytes>> + Unpin,
+{
+    let enc = Some(GzEncoder::new(Vec::new(), Compression::default()));
+    futures::stream::unfold((inner, enc), |(mut inner, mut enc)| async move {
+        loop {
+            let gz = enc.as_mut()?;
+            match inner.next().await {
+                Some(Ok(chunk)) => {
+                    if let Err(e) = gz.write_all(&chunk) {
+                        return Some((Err(e), (inner, None)));
+                    }
+                    let out = std::mem::take(gz.get_mut());
+                    if !out.is_empty() {
+                        return Some((Ok(Bytes::from(out)), (inner, enc)));
+                    }
+                }
+                Some(Err(e)) => return Some((Err(e), (inner, None))),
+                None => {
+                    let tail = enc.take()?.finish().map(Bytes::from);
+                    return Some((tail, (inner, None)));
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn gzip_stream_round_trips() {
+        let chunks =
+            ["first line\n", "", "second line\n"].map(|s| Ok(Bytes::from_static(s.as_bytes())));
+        let gz: Vec<Bytes> = gzip_stream(futures::stream::iter(chunks))
+            .map(|c| c.unwrap())
+            .collect()
+            .await;
+
+        let mut out = String::new();
+        GzDecoder::new(gz.concat().as_slice())
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "first line\nsecond line\n");
+    }
+
+    #[test]
+    fn strip_ansi_removes_escapes() {
+        assert_eq!(strip_ansi("\x1b[1;31mERROR\x1b[0m done"), "ERROR done");
+        assert_eq!(strip_ansi("\x1b]0;title\x07text"), "text");
+        assert_eq!(strip_ansi("\x1b]8;;http://x\x1b\\link"), "link");
+        assert_eq!(strip_ansi("\x1b(Bplain"), "plain");
+        assert_eq!(strip_ansi("cut \x1b[3"), "cut ");
+        assert_eq!(strip_ansi("no escapes ✓"), "no escapes ✓");
+    }
+
+    #[tokio::test]
+    async fn strip_ansi_stream_handles_splits_across_frames() {
+        let frames = [b"\x1b[1;3".as_slice(), b"1mred\x1b[0m caf\xc3", b"\xa9\n"].map(|b| {
+            Ok(LogOutput::StdOut {
+                message: Bytes::from_static(b),
+            })
+        });
+        let out: Vec<Bytes> = strip_ansi_stream(futures::stream::iter(frames))
+            .map(|c| c.unwrap())
+            .collect()
+            .await;
+        assert_eq!(String::from_utf8(out.concat()).unwrap(), "red café\n");
+    }
+
+    #[test]
+    fn splitter_caps_lines_without_newline() {
+        let mut splitter = LineSplitter::new(false);
+        // "é" straddles the cap: the piece stops before it
+        let mut message = vec![b'a'; MAX_LINE_BYTES - 1];
+        message.extend_from_slice("é tail".as_bytes());
+        let lines = splitter.push(LogOutput::StdOut {
+            message: Bytes::from(message),
+        });
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].line.len(), MAX_LINE_BYTES - 1);
+        assert_eq!(splitter.flush()[0].line, "é tail");
+    }
+
+    #[test]
+    fn splitter_strips_when_asked() {
+        let frame = || LogOutput::StdOut {
+            message: Bytes::from_static(b"\x1b[32mok\x1b[0m\n"),
+        };
+        assert_eq!(LineSplitter::new(true).push(frame())[0].line, "ok");
+        assert_eq!(
+            LineSplitter::new(false).push(frame())[0].line,
+            "\x1b[32mok\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn timestamp_prefix_is_split_off() {
+        let line = |s: &str| LogLine {
+            ts: None,
+            stream: "stdout",
+            line: s.to_owned(),
+        };
+
+        let parsed = line("2024-05-01T12:00:00.123456789Z hello world").split_timestamp();
+        assert_eq!(parsed.ts.as_deref(), Some("2024-05-01T12:00:00.123456789Z"));
+        assert_eq!(parsed.line, "hello world");
+
+        let raw = line("not-a-time hello").split_timestamp();
+        assert_eq!(raw.ts, None);
+        assert_eq!(raw.line, "not-a-time hello");
+    }
+}
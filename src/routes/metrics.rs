@@ -1,27 +1,367 @@
-use axum::{extract::State, response::IntoResponse};
-use std::{sync::Arc, time::Duration};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
+use crate::metric_registry::{MetricRegistry, MAX_WINDOW};
 use crate::state::AppState;
+use crate::stats::{collect_stats, Stats};
 
-pub async fn metrics_handler(State(app): State<Arc<AppState>>) -> impl IntoResponse {
-    // Build a plain-text exposition:
-    // rezn_cpu_usage_avg10{container="xyz"} 0.12
-    // rezn_mem_usage_max10{container="xyz"} 8.0e+08
-    let mut out = String::new();
-    for entry in app.metric_registry.cpu.iter() {
-        let id = entry.key();
-        if let Some(avg) = app.metric_registry.cpu_avg(id, Duration::from_secs(10)) {
-            out.push_str(&format!(
-                "rezn_cpu_usage_avg10{{container=\"{}\"}} {}\n",
-                id, avg
-            ));
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MetricsQuery {
+    /// Aggregation window in seconds (default 10, capped at 60); shows up in
+    /// the series names, e.g. `rezn_cpu_usage_avg30`
+    #[param(required = false)]
+    window: Option<u64>,
+}
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Text format for `/metrics`, picked from the scraper's `Accept` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExpositionFormat {
+    Prometheus,
+    OpenMetrics,
+}
+
+impl ExpositionFormat {
+    fn negotiate(headers: &HeaderMap) -> Self {
+        let wants_openmetrics = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .any(|v| v.contains("application/openmetrics-text"));
+        if wants_openmetrics {
+            Self::OpenMetrics
+        } else {
+            Self::Prometheus
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    params(MetricsQuery),
+    responses(
+        (status = 200, description = "Prometheus text exposition, or OpenMetrics when requested via `Accept: application/openmetrics-text`. Empty under `ORQOS_METRICS=off`", content_type = "text/plain", body = String)
+    ),
+    tag = "Metrics",
+)]
+pub async fn metrics_handler(
+    State(app): State<Arc<AppState>>,
+    Query(q): Query<MetricsQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let window = Duration::from_secs(q.window.unwrap_or(10).max(1)).min(MAX_WINDOW);
+    let format = ExpositionFormat::negotiate(&headers);
+    let poll = PollStatus {
+        last_ok: app.last_poll_ok.load(Ordering::Relaxed),
+        last_at_ms: app.last_poll_time.load(Ordering::Relaxed),
+        last_duration: Duration::from_micros(app.last_poll_duration_us.load(Ordering::Relaxed)),
+        errors: app.poll_errors.load(Ordering::Relaxed),
+    };
+    let out = if app.config.metrics_enabled {
+        render_exposition(
+            &app.metric_registry,
+            window,
+            app.events_dropped.load(Ordering::Relaxed),
+            &poll,
+            format,
+        )
+    } else {
+        empty_exposition(format).to_owned()
+    };
+    let content_type = match format {
+        ExpositionFormat::Prometheus => PROMETHEUS_CONTENT_TYPE,
+        ExpositionFormat::OpenMetrics => OPENMETRICS_CONTENT_TYPE,
+    };
+    ([(header::CONTENT_TYPE, content_type)], out)
+}
+
+/// The default (10s) `/metrics` aggregates as JSON, keyed by container ID.
+#[utoipa::path(
+    get,
+    path = "/metrics.json",
+    responses(
+        (status = 200, description = "Per-container aggregates keyed by ID", body = BTreeMap<String, Stats>)
+    ),
+    tag = "Metrics",
+)]
+pub async fn metrics_json_handler(
+    State(app): State<Arc<AppState>>,
+) -> Json<BTreeMap<String, Stats>> {
+    Json(collect_stats(&app.metric_registry, Duration::from_secs(10)))
+}
+
+/// Health of the metrics poll loop itself.
+#[derive(Debug, Default)]
+struct PollStatus {
+    last_ok: bool,
+    /// UNIX ms of the last poll; 0 if none ran yet
+    last_at_ms: u64,
+    last_duration: Duration,
+    errors: u64,
+}
+
+/// Build the plain-text exposition (here for a 10s window):
+/// rezn_cpu_usage_avg10{name="web",id="xyz",label_tier="backend"} 0.12
+/// rezn_mem_usage_max10{name="web",id="xyz",label_tier="backend"} 800000000
+///
+/// Each metric's series are kept together, as both formats require.
+fn render_exposition(
+    registry: &MetricRegistry,
+    window: Duration,
+    events_dropped: u64,
+    poll: &PollStatus,
+    format: ExpositionFormat,
+) -> String {
+    let secs = window.as_secs();
+
+    // Snapshot the ids once so no DashMap shard lock is held while rendering.
+    let ids: Vec<String> = registry.cpu.iter().map(|e| e.key().clone()).collect();
+    let labels: Vec<String> = ids.iter().map(|id| series_labels(registry, id)).collect();
+    // Two series per container at roughly 160 bytes each, plus the footer.
+    let mut out = String::with_capacity(ids.len() * 320 + 128);
+
+    for (id, labels) in ids.iter().zip(&labels) {
+        // Prometheus rejects the whole scrape on Rust's `NaN`/`inf`; skip them.
+        if let Some(avg) = registry.cpu_avg(id, window).filter(|v| v.is_finite()) {
+            let _ = writeln!(out, "rezn_cpu_usage_avg{secs}{{{labels}}} {avg}");
+        }
+    }
+    for (id, labels) in ids.iter().zip(&labels) {
+        // Integer bytes, never scientific notation.
+        if let Some(max_mem) = registry.mem_max(id, window) {
+            let _ = writeln!(out, "rezn_mem_usage_max{secs}{{{labels}}} {max_mem}");
         }
-        if let Some(max_mem) = app.metric_registry.mem_max(id, Duration::from_secs(10)) {
-            out.push_str(&format!(
-                "rezn_mem_usage_max10{{container=\"{}\"}} {}\n",
-                id, max_mem
-            ));
+    }
+    for (id, labels) in ids.iter().zip(&labels) {
+        // `paused` is never pruned; a container with no sample in the window
+        // is gone (or not polled), and its flag is stale.
+        if registry.cpu_avg(id, window).is_none() {
+            continue;
+        }
+        if let Some(paused) = registry.is_paused(id) {
+            let _ = writeln!(
+                out,
+                "rezn_container_paused{{{labels}}} {}",
+                u8::from(paused)
+            );
         }
     }
-    ([(axum::http::header::CONTENT_TYPE, "text/plain")], out)
+    if let Some(host) = registry.host_gauges() {
+        let _ = writeln!(out, "rezn_containers_running {}", host.containers_running);
+        let _ = writeln!(out, "rezn_containers_total {}", host.containers_total);
+        let _ = writeln!(out, "rezn_images_total {}", host.images_total);
+    }
+    let _ = writeln!(out, "rezn_poll_errors_total {}", poll.errors);
+    if poll.last_at_ms != 0 {
+        let _ = writeln!(out, "rezn_metrics_poll_ok {}", u8::from(poll.last_ok));
+        let _ = writeln!(
+            out,
+            "rezn_last_poll_timestamp {}",
+            poll.last_at_ms as f64 / 1000.0
+        );
+        let _ = writeln!(
+            out,
+            "rezn_poll_duration_seconds {}",
+            poll.last_duration.as_secs_f64()
+        );
+    }
+    let _ = writeln!(out, "rezn_events_dropped_total {events_dropped}");
+
+    if format == ExpositionFormat::OpenMetrics {
+        out.push_str("# EOF\n");
+    }
+    out
+}
+
+/// A scrape with no series, for when the poller is off (`ORQOS_METRICS=off`).
+fn empty_exposition(format: ExpositionFormat) -> &'static str {
+    match format {
+        ExpositionFormat::Prometheus => "",
+        ExpositionFormat::OpenMetrics => "# EOF\n",
+    }
+}
+
+/// Render the label set for one container's series (without the braces).
+fn series_labels(registry: &MetricRegistry, id: &str) -> String {
+    let mut out = format!(
+        "name=\"{}\",id=\"{}\"",
+        escape_label_value(&registry.display_name(id)),
+        escape_label_value(id)
+    );
+
+    if let Some(labels) = registry.labels.get(id) {
+        for (k, v) in labels.iter() {
+            let _ = write!(
+                out,
+                ",label_{}=\"{}\"",
+                sanitize_label_name(k),
+                escape_label_value(v)
+            );
+        }
+    }
+
+    out
+}
+
+/// Docker label keys (`com.example.tier`) aren't valid Prometheus label names.
+fn sanitize_label_name(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn escape_label_value(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::metric_registry::Sample;
+
+    use super::*;
+
+    #[test]
+    fn non_finite_cpu_is_excluded() {
+        let registry = MetricRegistry::default();
+        registry.record_cpu("nan", f64::NAN);
+        registry.record_cpu("inf", f64::INFINITY);
+        registry.record_cpu("ok", 0.25);
+        registry.record_mem("ok", 800_000_000);
+
+        let out = render_exposition(
+            &registry,
+            Duration::from_secs(10),
+            0,
+            &PollStatus::default(),
+            ExpositionFormat::Prometheus,
+        );
+
+        assert!(!out.contains("NaN") && !out.contains("inf"), "{out}");
+        assert!(!out.contains("rezn_cpu_usage_avg10{name=\"nan\""), "{out}");
+        assert!(out.contains("rezn_cpu_usage_avg10{name=\"ok\",id=\"ok\"} 0.25\n"));
+        assert!(out.contains("rezn_mem_usage_max10{name=\"ok\",id=\"ok\"} 800000000\n"));
+        assert!(!out.contains("# EOF"));
+    }
+
+    #[test]
+    fn openmetrics_is_negotiated_and_terminated() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            "application/openmetrics-text;version=1.0.0,text/plain;q=0.5"
+                .parse()
+                .unwrap(),
+        );
+        let format = ExpositionFormat::negotiate(&headers);
+        assert_eq!(format, ExpositionFormat::OpenMetrics);
+        assert_eq!(
+            ExpositionFormat::negotiate(&HeaderMap::new()),
+            ExpositionFormat::Prometheus
+        );
+
+        let out = render_exposition(
+            &MetricRegistry::default(),
+            Duration::from_secs(10),
+            3,
+            &PollStatus::default(),
+            format,
+        );
+        assert!(
+            out.ends_with("rezn_events_dropped_total 3\n# EOF\n"),
+            "{out}"
+        );
+    }
+
+    #[test]
+    fn poll_status_is_exposed_once_a_poll_ran() {
+        let out = render_exposition(
+            &MetricRegistry::default(),
+            Duration::from_secs(10),
+            0,
+            &PollStatus {
+                last_ok: false,
+                last_at_ms: 1_700_000_000_500,
+                last_duration: Duration::from_millis(250),
+                errors: 2,
+            },
+            ExpositionFormat::Prometheus,
+        );
+        assert!(out.contains("rezn_metrics_poll_ok 0\n"), "{out}");
+        assert!(
+            out.contains("rezn_last_poll_timestamp 1700000000.5\n"),
+            "{out}"
+        );
+        assert!(out.contains("rezn_poll_duration_seconds 0.25\n"), "{out}");
+        assert!(out.contains("rezn_poll_errors_total 2\n"), "{out}");
+    }
+
+    #[test]
+    fn disabled_metrics_still_parse() {
+        assert_eq!(empty_exposition(ExpositionFormat::Prometheus), "");
+        assert_eq!(empty_exposition(ExpositionFormat::OpenMetrics), "# EOF\n");
+    }
+
+    #[test]
+    fn paused_gauge_per_container() {
+        let registry = MetricRegistry::default();
+        registry.record_cpu("abc", 0.0);
+        registry.record_paused("abc", true);
+        registry.record_cpu("def", 0.5);
+
+        let out = render_exposition(
+            &registry,
+            Duration::from_secs(10),
+            0,
+            &PollStatus::default(),
+            ExpositionFormat::Prometheus,
+        );
+        assert!(
+            out.contains("rezn_container_paused{name=\"abc\",id=\"abc\"} 1\n"),
+            "{out}"
+        );
+        assert!(!out.contains("rezn_container_paused{name=\"def\""), "{out}");
+    }
+
+    #[test]
+    fn paused_gauge_skips_containers_without_recent_samples() {
+        let registry = MetricRegistry::default();
+        registry
+            .cpu
+            .entry("gone".into())
+            .or_default()
+            .push_back(Sample {
+                at: Instant::now().checked_sub(Duration::from_secs(30)).unwrap(),
+                wall_ms: 0,
+                value: 0.1,
+            });
+        registry.record_paused("gone", false);
+
+        let out = render_exposition(
+            &registry,
+            Duration::from_secs(10),
+            0,
+            &PollStatus::default(),
+            ExpositionFormat::Prometheus,
+        );
+        assert!(!out.contains("rezn_container_paused"), "{out}");
+    }
 }
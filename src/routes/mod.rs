@@ -1,10 +1,31 @@
+pub mod attach;
+pub mod container_commit;
 pub mod container_create;
+pub mod container_create_stream;
+pub mod container_env;
+pub mod container_export;
+pub mod container_health;
+pub mod container_history;
+pub mod container_id;
+pub mod container_recreate;
 pub mod container_remove;
+pub mod container_restart_policy;
+pub mod container_stats;
 pub mod container_stop;
+pub mod containers_bulk;
 pub mod containers_list;
+pub mod delete_file;
+pub mod docker_passthrough;
 pub mod events_ws;
 pub mod exec;
+pub mod images;
+pub mod jobs;
+pub mod list_dir;
+pub mod logs;
 pub mod metrics;
 pub mod read_file;
+pub mod readyz;
+pub mod stacks;
 pub mod stats_ws;
+pub mod version;
 pub mod write_file;
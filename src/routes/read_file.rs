@@ -1,11 +1,14 @@
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     http::{self, HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
 };
-use bollard::query_parameters::DownloadFromContainerOptions;
+use bollard::{
+    errors::Error as BollardError, query_parameters::DownloadFromContainerOptions, Docker,
+};
 use flate2::read::GzDecoder;
 use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
 use std::path::Path as StdPath;
 use std::{
     env,
@@ -45,27 +48,120 @@ fn clean_path(raw: &str) -> Result<PathBuf, &'static str> {
     Ok(out)
 }
 
+/// Normalise `raw` and apply the read policy (`ORQOS_READ_BASE` prefix plus
+/// the system-dir ban list), returning the path to hand to the daemon.
+pub(crate) fn resolve_readable_path(raw: &str) -> Result<String, (StatusCode, String)> {
+    let base = allowed_base();
+    let target: PathBuf = clean_path(raw).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    // prefix check (string compare is fine – both are absolute & normalised)
+    if !target.starts_with(&base) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "path outside allowed base directory".into(),
+        ));
+    }
+
+    // optional hard ban list
+    let ban = ["/etc", "/proc", "/sys", "/dev", "/var/run"];
+    for bad in ban {
+        if target.starts_with(bad) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "access to system dirs forbidden".into(),
+            ));
+        }
+    }
+
+    target.to_str().map(str::to_owned).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid UTF-8 path in request".to_string(),
+        )
+    })
+}
+
 #[derive(Debug, serde::Deserialize, ToSchema)]
 pub struct ReadFileRequest {
     /// Absolute path inside the container
     pub path: String,
 }
 
+#[derive(Debug, Default, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReadFileQuery {
+    /// Expected hex SHA-256 of the file; a mismatch yields 422
+    #[param(required = false)]
+    pub sha256: Option<String>,
+    /// `auto` (default) guesses the type from the content, `text` forces
+    /// `text/plain`, `binary` forces `application/octet-stream`. Overrides
+    /// the `Accept` header.
+    #[serde(rename = "as")]
+    #[param(rename = "as", required = false)]
+    pub as_: Option<ServeAs>,
+}
+
+/// How read-file labels the bytes it returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ServeAs {
+    Auto,
+    Text,
+    Binary,
+}
+
+impl ServeAs {
+    /// `?as=` if given, else whatever `Accept` asks for explicitly. Wildcards
+    /// and other types leave it to `auto`.
+    fn pick(query: Option<Self>, headers: &HeaderMap) -> Self {
+        if let Some(as_) = query {
+            return as_;
+        }
+        headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|t| t.split(';').next().unwrap_or("").trim())
+            .find_map(|t| match t {
+                "text/plain" => Some(Self::Text),
+                "application/octet-stream" => Some(Self::Binary),
+                _ => None,
+            })
+            .unwrap_or(Self::Auto)
+    }
+
+    fn content_type(self, content: &[u8]) -> &'static str {
+        match self {
+            Self::Auto => infer::get(content)
+                .map(|t| t.mime_type())
+                .unwrap_or("application/octet-stream"),
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Binary => "application/octet-stream",
+        }
+    }
+}
+
 /// Pull a single file out of a container.
 ///
 /// `POST /containers/{id}/read-file`  
 /// Body: `{ "path": "/absolute/path" }`  
-/// Response: `200` *application/octet-stream*
+/// Response: `200` with the raw bytes, typed per `?as=` / `Accept`  
+/// Carries a weak `ETag` (size + mtime); a matching `If-None-Match` gets `304`.
 #[utoipa::path(
     post,
-    path = "/containers/{id}/read_file",
+    path = "/containers/{id}/read-file",
     request_body = ReadFileRequest,
     params(
-        ("id" = String, Path, description = "Container ID or name")
+        ("id" = String, Path, description = "Container ID or name"),
+        ReadFileQuery,
     ),
     responses(
-        (status = 200, description = "Raw file bytes", content_type = "application/octet-stream"),
+        (status = 200, description = "Raw file bytes; the type is inferred from the content unless `as` or `Accept` asks for `text/plain` or `application/octet-stream`", content_type = "application/octet-stream"),
+        (status = 304, description = "`If-None-Match` matches the file's current ETag"),
         (status = 404, description = "File not found"),
+        (status = 413, description = "File larger than `ORQOS_MAX_READ_BYTES`"),
+        (status = 422, description = "Downloaded file does not match the expected `sha256`"),
         (status = 500, description = "Docker or server error", body = String)
     ),
     tag = "Containers",
@@ -73,72 +169,121 @@ pub struct ReadFileRequest {
 pub async fn read_file_handler(
     State(state): State<Arc<AppState>>,
     Path(container): Path<String>,
+    Query(q): Query<ReadFileQuery>,
+    request_headers: HeaderMap,
     Json(req): Json<ReadFileRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let base = allowed_base();
-    let target: PathBuf =
-        clean_path(&req.path).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let serve_as = ServeAs::pick(q.as_, &request_headers);
+    let path = resolve_readable_path(&req.path)?;
 
-    // prefix check (string compare is fine – both are absolute & normalised)
-    if !target.starts_with(&base) {
-        return Err((
-            StatusCode::FORBIDDEN,
-            format!("path outside allowed base directory"),
-        ));
+    let mut hasher = q.sha256.as_ref().map(|_| Sha256::new());
+    let (content, stamp) = download_file_stamped(
+        &state.docker,
+        &container,
+        &path,
+        state.config.max_read_bytes,
+        hasher.as_mut(),
+    )
+    .await?;
+
+    let etag = HeaderValue::from_str(&stamp.etag()).ok();
+    if let Some(etag) = etag.as_ref() {
+        if stamp.matches(&request_headers) {
+            return Ok((
+                StatusCode::NOT_MODIFIED,
+                [(http::header::ETAG, etag.clone())],
+            )
+                .into_response());
+        }
     }
 
-    // optional hard ban list
-    let ban = ["/etc", "/proc", "/sys", "/dev", "/var/run"];
-    for bad in ban {
-        if target.starts_with(bad) {
+    let digest = hasher.map(|h| format!("{:x}", h.finalize()));
+    if let (Some(expected), Some(actual)) = (&q.sha256, &digest) {
+        if !expected.eq_ignore_ascii_case(actual) {
             return Err((
-                StatusCode::FORBIDDEN,
-                "access to system dirs forbidden".into(),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("sha256 mismatch: expected {expected}, got {actual}"),
             ));
         }
     }
 
-    // 1) Ask the daemon for a tar archive containing `req.path`
-    let opts = DownloadFromContainerOptions {
-        path: target
-            .to_str()
-            .ok_or_else(|| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    "Invalid UTF-8 path in request".into(),
-                )
-            })?
-            .to_string(),
-        ..Default::default()
-    };
+    let mime = serve_as.content_type(&content);
 
-    // Await the API call
-    let mut stream = state.docker.download_from_container(&container, Some(opts));
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        http::header::CONTENT_TYPE,
+        HeaderValue::from_str(mime).unwrap_or(HeaderValue::from_static("application/octet-stream")),
+    );
+    if let Some(digest) = digest.and_then(|d| HeaderValue::from_str(&d).ok()) {
+        headers.insert("x-content-sha256", digest);
+    }
+    if let Some(etag) = etag {
+        headers.insert(http::header::ETAG, etag);
+    }
+    Ok((headers, content).into_response())
+}
 
-    // Slurp the tar stream into memory
-    let mut tar_bytes = Vec::new();
-    while let Some(chunk) = stream.next().await {
-        tar_bytes.extend_from_slice(
-            &chunk.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
-        );
+/// Size and mtime from a file's tar header: enough for a weak validator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    pub size: u64,
+    /// Seconds since the UNIX epoch
+    pub mtime: u64,
+}
+
+impl FileStamp {
+    pub fn etag(&self) -> String {
+        format!("W/\"{:x}-{:x}\"", self.size, self.mtime)
     }
 
-    // Check gzip magic on tar_bytes directly
-    let is_gz = tar_bytes.starts_with(&[0x1F, 0x8B]); // gzip magic
-    let cursor = Cursor::new(tar_bytes);
-    let reader: Box<dyn Read> = if is_gz {
-        Box::new(GzDecoder::new(cursor))
-    } else {
-        Box::new(cursor)
-    };
-    let mut archive = Archive::new(reader);
+    /// Whether `If-None-Match` lists this stamp's ETag (or `*`). Comparison
+    /// is weak, so a strong `"…"` form of the same tag also matches.
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        let etag = self.etag();
+        let ours = etag.trim_start_matches("W/");
+        headers
+            .get_all(http::header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == ours)
+    }
+}
+
+/// Fetch the single regular file at `path` from `container`, refusing (413)
+/// anything that decompresses to more than `max_bytes`.
+///
+/// No path policy is applied here; callers are responsible for that.
+pub(crate) async fn download_file(
+    docker: &Docker,
+    container: &str,
+    path: &str,
+    max_bytes: usize,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    download_file_stamped(docker, container, path, max_bytes, None)
+        .await
+        .map(|(content, _)| content)
+}
+
+/// [`download_file`], plus the size and mtime recorded in the tar header.
+/// The content is fed to `hasher` as it's read.
+pub(crate) async fn download_file_stamped(
+    docker: &Docker,
+    container: &str,
+    path: &str,
+    max_bytes: usize,
+    mut hasher: Option<&mut Sha256>,
+) -> Result<(Vec<u8>, FileStamp), (StatusCode, String)> {
+    let tar_bytes = download_tar(docker, container, path, max_bytes).await?;
+    let mut archive = open_archive(tar_bytes);
 
     // Expect exactly one entry inside
     let mut entries = archive
         .entries()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut file = entries
+    let file = entries
         .next()
         .ok_or((StatusCode::NOT_FOUND, "File not found".into()))?
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -154,18 +299,167 @@ pub async fn read_file_handler(
         ));
     }
 
+    let stamp = FileStamp {
+        size: file.header().size().unwrap_or(0),
+        mtime: file.header().mtime().unwrap_or(0),
+    };
+
+    let too_large = || {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("file exceeds {max_bytes} bytes"),
+        )
+    };
+    // The header's size is cheap to check, but it's attacker-controlled:
+    // the limited read below is what actually bounds memory.
+    if stamp.size > max_bytes as u64 {
+        return Err(too_large());
+    }
+
+    // Hash while copying out of the archive so verification needs no second pass.
     let mut content = Vec::new();
-    file.read_to_end(&mut content)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut file = file.take(max_bytes as u64 + 1);
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut chunk)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        if let Some(h) = hasher.as_deref_mut() {
+            h.update(&chunk[..n]);
+        }
+        content.extend_from_slice(&chunk[..n]);
+    }
+    if content.len() > max_bytes {
+        return Err(too_large());
+    }
 
-    let mime = infer::get(&content)
-        .map(|t| t.mime_type())
-        .unwrap_or("application/octet-stream");
+    Ok((content, stamp))
+}
 
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        http::header::CONTENT_TYPE,
-        HeaderValue::from_str(mime).unwrap_or(HeaderValue::from_static("application/octet-stream")),
-    );
-    Ok((headers, content))
+/// Slack on top of the content limit for tar framing: entry headers (plus
+/// PAX/long-name headers), block padding and the end-of-archive marker.
+const TAR_OVERHEAD: usize = 64 * 1024;
+
+/// Ask the daemon for a tar archive containing `path` and buffer it.
+/// A missing container or path maps to 404; an archive that grows past
+/// `max_bytes` (plus tar framing) is abandoned with 413 as soon as it does.
+pub(crate) async fn download_tar(
+    docker: &Docker,
+    container: &str,
+    path: &str,
+    max_bytes: usize,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let limit = max_bytes.saturating_add(TAR_OVERHEAD);
+    let opts = DownloadFromContainerOptions {
+        path: path.to_string(),
+    };
+
+    // Await the API call
+    let mut stream = docker.download_from_container(container, Some(opts));
+
+    // Slurp the tar stream into memory
+    let mut tar_bytes = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| match e {
+            BollardError::DockerResponseServerError {
+                status_code: 404,
+                message,
+            } => (StatusCode::NOT_FOUND, message),
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+        if tar_bytes.len() + chunk.len() > limit {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("{path} exceeds {max_bytes} bytes"),
+            ));
+        }
+        tar_bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(tar_bytes)
+}
+
+/// Wrap downloaded tar bytes in an [`Archive`], transparently gunzipping.
+pub(crate) fn open_archive(tar_bytes: Vec<u8>) -> Archive<Box<dyn Read>> {
+    // Check gzip magic on tar_bytes directly
+    let is_gz = tar_bytes.starts_with(&[0x1F, 0x8B]); // gzip magic
+    let cursor = Cursor::new(tar_bytes);
+    let reader: Box<dyn Read> = if is_gz {
+        Box::new(GzDecoder::new(cursor))
+    } else {
+        Box::new(cursor)
+    };
+    Archive::new(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(v: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::ACCEPT, HeaderValue::from_static(v));
+        headers
+    }
+
+    #[test]
+    fn query_overrides_accept() {
+        let headers = accept("text/plain");
+        assert_eq!(
+            ServeAs::pick(Some(ServeAs::Binary), &headers),
+            ServeAs::Binary
+        );
+        assert_eq!(ServeAs::pick(None, &headers), ServeAs::Text);
+    }
+
+    #[test]
+    fn accept_wildcards_fall_back_to_auto() {
+        assert_eq!(ServeAs::pick(None, &HeaderMap::new()), ServeAs::Auto);
+        assert_eq!(ServeAs::pick(None, &accept("*/*")), ServeAs::Auto);
+        assert_eq!(
+            ServeAs::pick(None, &accept("text/html, application/octet-stream;q=0.9")),
+            ServeAs::Binary
+        );
+    }
+
+    #[test]
+    fn content_type_per_mode() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(ServeAs::Auto.content_type(png), "image/png");
+        assert_eq!(
+            ServeAs::Binary.content_type(png),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            ServeAs::Text.content_type(b"hi"),
+            "text/plain; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn if_none_match_is_weak() {
+        let stamp = FileStamp {
+            size: 10,
+            mtime: 255,
+        };
+        assert_eq!(stamp.etag(), "W/\"a-ff\"");
+
+        let mut headers = HeaderMap::new();
+        assert!(!stamp.matches(&headers));
+        headers.insert(
+            http::header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"other\", \"a-ff\""),
+        );
+        assert!(stamp.matches(&headers));
+        headers.insert(
+            http::header::IF_NONE_MATCH,
+            HeaderValue::from_static("W/\"a-fe\""),
+        );
+        assert!(!stamp.matches(&headers));
+        headers.insert(http::header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(stamp.matches(&headers));
+    }
 }
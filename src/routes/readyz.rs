@@ -0,0 +1,23 @@
+use std::sync::{atomic::Ordering, Arc};
+
+use axum::{extract::State, http::StatusCode};
+
+use crate::state::AppState;
+
+/// Readiness probe: 200 once the Docker daemon has answered, 503 before.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "Docker daemon reachable", body = String),
+        (status = 503, description = "Still waiting for the Docker daemon", body = String)
+    ),
+    tag = "Health",
+)]
+pub async fn readyz_handler(State(app): State<Arc<AppState>>) -> (StatusCode, &'static str) {
+    if app.docker_ready.load(Ordering::Acquire) {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "waiting for docker")
+    }
+}
@@ -0,0 +1,365 @@
+//! Multi-container stacks: a minimal, compose-like way to start several
+//! services on a shared network in one call.
+//! -----------------------------------------------------------
+//! * REST   POST   /stacks         → create network + start every service
+//! * REST   DELETE /stacks/{name}  → stop/remove its containers and network
+//!
+//! Everything a stack creates carries the `orqos.stack=<name>` label; that
+//! is how teardown finds it again.
+//! -----------------------------------------------------------
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use bollard::{
+    errors::Error as BollardError,
+    models::NetworkCreateRequest,
+    query_parameters::{
+        InspectNetworkOptions, ListContainersOptionsBuilder, RemoveContainerOptions,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::{
+    routes::{
+        container_create::{
+            managed_labels, prepare_container, start_prepared_with, ContainerCreate, ContainerInfo,
+            PreparedContainer,
+        },
+        container_id::validate_container_id,
+        container_remove::remove_container,
+        container_stop::stop_container,
+        containers_bulk::{for_each, BulkResult},
+    },
+    state::AppState,
+};
+
+/// Label put on a stack's network and containers, holding the stack name.
+const STACK_LABEL: &str = "orqos.stack";
+/// Label holding the service name on a stack's containers.
+const SERVICE_LABEL: &str = "orqos.stack.service";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StackCreate {
+    /// Names the network; containers are named `<name>-<service>`
+    pub name: String,
+    /// Service name → create body as for `POST /containers`, minus `name`
+    /// and `network`, which the stack sets
+    #[schema(value_type = HashMap<String, ContainerCreate>)]
+    pub services: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StackInfo {
+    pub name: String,
+    /// ID of the stack's network
+    pub network_id: String,
+    pub services: BTreeMap<String, ContainerInfo>,
+}
+
+/// Create a network named after the stack, then create and start each
+/// service on it (in name order), reachable from the others by its service
+/// name.
+///
+/// Every service is validated before anything is created. If one fails to
+/// start, the containers already started and the network are removed again.
+#[utoipa::path(
+    post,
+    path = "/stacks",
+    request_body(content = StackCreate, example = json!({
+        "name": "blog",
+        "services": {
+            "db": { "image": "postgres:16", "env": ["POSTGRES_PASSWORD=secret"] },
+            "web": { "image": "ghost:5", "env": ["database__connection__host=db"], "ports": [{ "container": 2368 }] }
+        }
+    })),
+    responses(
+        (status = 200, description = "Stack created and started", body = StackInfo),
+        (status = 400, description = "Invalid stack or service name, a service sets `name` or `network`, or a service body is invalid"),
+        (status = 409, description = "A network or container with the stack's names already exists"),
+        (status = 500, description = "Docker or server error; anything created has been removed", body = String),
+    ),
+    tag = "Containers",
+)]
+pub async fn create_stack_handler(
+    State(app): State<Arc<AppState>>,
+    Json(req): Json<StackCreate>,
+) -> Result<Json<StackInfo>, (StatusCode, String)> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    validate_container_id(&req.name).map_err(|e| bad(format!("stack name: {e}")))?;
+    if req.services.is_empty() {
+        return Err(bad("a stack needs at least one service".into()));
+    }
+
+    // Validate everything before touching Docker
+    let mut prepared: Vec<(String, PreparedContainer)> = Vec::new();
+    for (service, body) in req.services {
+        let container = format!("{}-{service}", req.name);
+        validate_container_id(&container).map_err(|e| bad(format!("service {service}: {e}")))?;
+
+        let Value::Object(mut body) = body else {
+            return Err(bad(format!("service {service}: expected an object")));
+        };
+        if body.contains_key("name") || body.contains_key("network") {
+            return Err(bad(format!(
+                "service {service}: name and network are set by the stack"
+            )));
+        }
+        body.insert("name".into(), container.clone().into());
+        body.insert("network".into(), req.name.clone().into());
+
+        let mut spec: ContainerCreate = serde_json::from_value(Value::Object(body))
+            .map_err(|e| bad(format!("service {service}: {e}")))?;
+        let labels = spec.labels.get_or_insert_with(HashMap::new);
+        labels.insert(STACK_LABEL.into(), req.name.clone());
+        labels.insert(SERVICE_LABEL.into(), service.clone());
+
+        let ready = prepare_container(&app, spec)
+            .map_err(|(status, msg)| (status, format!("service {service}: {msg}")))?
+            .with_network_alias(&req.name, &service);
+        prepared.push((service, ready));
+    }
+
+    let network_id = app
+        .docker
+        .create_network(NetworkCreateRequest {
+            name: req.name.clone(),
+            driver: Some("bridge".into()),
+            labels: Some(managed_labels(Some(HashMap::from([(
+                STACK_LABEL.into(),
+                req.name.clone(),
+            )])))),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| daemon_err(format!("creating network {}", req.name), e))?
+        .id;
+
+    let mut services = BTreeMap::new();
+    for (service, ready) in prepared {
+        let mut created_it = false;
+        let started = start_prepared_with(&app, ready, |step| {
+            created_it |= step == "starting";
+        })
+        .await;
+        match started {
+            Ok(info) => {
+                services.insert(service, info);
+            }
+            Err((status, msg)) => {
+                // Only clean up this service's container if we made it; a
+                // create that failed on a name clash left someone else's.
+                let mut created: Vec<String> = services.into_values().map(|c| c.id).collect();
+                if created_it {
+                    created.push(format!("{}-{service}", req.name));
+                }
+                roll_back(&app, &req.name, &network_id, &created).await;
+                return Err((
+                    status,
+                    format!("service {service} failed, stack rolled back: {msg}"),
+                ));
+            }
+        }
+    }
+
+    tracing::info!(stack = req.name, services = services.len(), "created stack");
+    Ok(Json(StackInfo {
+        name: req.name,
+        network_id,
+        services,
+    }))
+}
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TeardownQuery {
+    /// Seconds each container gets to stop before it is killed (default 10,
+    /// capped by `ORQOS_MAX_STOP_TIMEOUT`)
+    #[param(required = false)]
+    t: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StackTeardown {
+    pub name: String,
+    /// One entry per container found, in listing order
+    pub containers: Vec<BulkResult>,
+    /// `false` if there was no network left to remove or removing it failed
+    pub network_removed: bool,
+    /// Why the network could not be removed, if it exists but stayed
+    /// (including when a same-named network isn't the stack's)
+    pub network_error: Option<String>,
+}
+
+/// Stop and remove every container labelled with the stack's name, then its
+/// network. Containers are handled independently, so one failure doesn't
+/// stop the rest; the network is removed last (and only goes once nothing
+/// is attached to it), and only if its own `orqos.stack` label names this
+/// stack.
+#[utoipa::path(
+    delete,
+    path = "/stacks/{name}",
+    params(
+        ("name" = String, Path, description = "Stack name"),
+        TeardownQuery,
+    ),
+    responses(
+        (status = 200, description = "What was torn down", body = StackTeardown),
+        (status = 400, description = "Invalid stack name"),
+        (status = 404, description = "No containers or network belong to this stack"),
+        (status = 500, description = "Docker or server error", body = String),
+    ),
+    tag = "Containers",
+)]
+pub async fn delete_stack_handler(
+    State(app): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(q): Query<TeardownQuery>,
+) -> Result<Json<StackTeardown>, (StatusCode, String)> {
+    let name = validate_container_id(&name)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("stack name: {e}")))?
+        .to_owned();
+
+    let label = format!("{STACK_LABEL}={name}");
+    let filters = HashMap::from([("label", vec![label])]);
+    let opts = ListContainersOptionsBuilder::new()
+        .all(true)
+        .filters(&filters)
+        .build();
+    let found = app
+        .docker
+        .list_containers(Some(opts))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let t = Some(q.t.unwrap_or(10).min(app.config.max_stop_timeout));
+    let containers = for_each(found, |id| {
+        let app = app.clone();
+        async move {
+            match stop_container(&app.docker, &id, t, None).await {
+                // 304: already stopped
+                Ok(())
+                | Err(BollardError::DockerResponseServerError {
+                    status_code: 304, ..
+                }) => {}
+                Err(e) => return Err(e),
+            }
+            remove_container(&app.docker, &id, Some(true), None).await
+        }
+    })
+    .await;
+
+    let network = remove_stack_network(&app, &name).await;
+    let stack_network = matches!(network, NetworkOutcome::Removed | NetworkOutcome::Failed(_));
+    let (network_removed, network_error) = match network {
+        NetworkOutcome::Removed => (true, None),
+        NetworkOutcome::Failed(e) => (false, Some(e)),
+        NetworkOutcome::Missing => (false, None),
+        NetworkOutcome::NotOurs => (
+            false,
+            Some(format!(
+                "network {name} is not part of the stack ({STACK_LABEL} label missing or different); left alone"
+            )),
+        ),
+    };
+    if containers.is_empty() && !stack_network {
+        return Err((StatusCode::NOT_FOUND, format!("no stack named {name}")));
+    }
+
+    tracing::info!(
+        stack = name,
+        containers = containers.len(),
+        network_removed,
+        "tore down stack"
+    );
+    Ok(Json(StackTeardown {
+        name,
+        containers,
+        network_removed,
+        network_error,
+    }))
+}
+
+enum NetworkOutcome {
+    Removed,
+    Failed(String),
+    /// No network by that name
+    Missing,
+    /// A network by that name exists but wasn't created for this stack
+    NotOurs,
+}
+
+/// Remove the network named after the stack, but only if its
+/// `orqos.stack` label says it belongs to it; an unrelated network that
+/// happens to share the name is left alone.
+async fn remove_stack_network(app: &AppState, name: &str) -> NetworkOutcome {
+    let network = match app
+        .docker
+        .inspect_network(name, None::<InspectNetworkOptions>)
+        .await
+    {
+        Ok(network) => network,
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => return NetworkOutcome::Missing,
+        Err(e) => return NetworkOutcome::Failed(e.to_string()),
+    };
+    let ours = network
+        .labels
+        .as_ref()
+        .and_then(|l| l.get(STACK_LABEL))
+        .is_some_and(|v| v == name);
+    if !ours {
+        return NetworkOutcome::NotOurs;
+    }
+
+    // By ID, so a network swapped in under the same name since the inspect
+    // isn't removed instead
+    let id = network.id.as_deref().unwrap_or(name);
+    match app.docker.remove_network(id).await {
+        Ok(()) => NetworkOutcome::Removed,
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => NetworkOutcome::Missing,
+        Err(e) => NetworkOutcome::Failed(e.to_string()),
+    }
+}
+
+/// Best-effort removal of `containers` and then the stack's network.
+async fn roll_back(app: &AppState, stack: &str, network_id: &str, containers: &[String]) {
+    for id in containers {
+        let opts = RemoveContainerOptions {
+            force: true,
+            ..Default::default()
+        };
+        match app.docker.remove_container(id, Some(opts)).await {
+            Ok(())
+            | Err(BollardError::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(e) => tracing::warn!(stack, container = id, "rollback: {e}"),
+        }
+    }
+    if let Err(e) = app.docker.remove_network(network_id).await {
+        tracing::warn!(stack, "rollback: removing network: {e}");
+    }
+}
+
+/// Keep the daemon's 409 (name taken) apart from other failures.
+fn daemon_err(what: String, err: BollardError) -> (StatusCode, String) {
+    match err {
+        BollardError::DockerResponseServerError {
+            status_code: 409,
+            message,
+        } => (StatusCode::CONFLICT, format!("{what}: {message}")),
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("{what}: {e}")),
+    }
+}
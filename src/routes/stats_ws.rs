@@ -1,22 +1,36 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use axum::{
-    extract::{ws::Message, State, WebSocketUpgrade},
+    extract::{
+        ws::{Message, WebSocket},
+        Query, State, WebSocketUpgrade,
+    },
+    http::StatusCode,
     response::IntoResponse,
 };
+use futures::SinkExt;
+use serde::Deserialize;
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::{interval, MissedTickBehavior},
+};
 
-use crate::state::AppState;
+use crate::{routes::container_stats::METRICS_DISABLED, state::AppState};
 
 #[utoipa::path(
     get,
     path = "/stats/ws",
     description = "Exposes container stats via WS",
     responses(
-        (status = 101, description = "WebSocket upgrade initiated")
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 404, description = "Metrics are disabled (`ORQOS_METRICS=off`)")
     ),
     tag = "Streaming"
 )]
 pub async fn stats_ws(State(app): State<Arc<AppState>>, ws: WebSocketUpgrade) -> impl IntoResponse {
+    if !app.config.metrics_enabled {
+        return (StatusCode::NOT_FOUND, METRICS_DISABLED).into_response();
+    }
     ws.on_upgrade(move |mut socket| async move {
         let mut rx = app.stats_tx.subscribe();
         while let Ok(ev) = rx.recv().await {
@@ -24,4 +38,71 @@ pub async fn stats_ws(State(app): State<Arc<AppState>>, ws: WebSocketUpgrade) ->
             let _ = socket.send(Message::Text(ev.to_string().into())).await;
         }
     })
+    .into_response()
+}
+
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MetricsWsQuery {
+    /// Seconds between snapshots (5–300, default 5). Stats are polled every
+    /// 5s, so shorter intervals would only repeat the same data.
+    #[param(required = false)]
+    interval: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics/ws",
+    params(MetricsWsQuery),
+    responses(
+        (status = 101, description = "WebSocket upgrade initiated"),
+        (status = 404, description = "Metrics are disabled (`ORQOS_METRICS=off`)")
+    ),
+    tag = "Streaming",
+    summary = "Follow the whole fleet's stats over one WebSocket",
+    description = "Sends the latest snapshot of every container's `cpu_avg`/`max_mem`, keyed by ID (the `/stats/ws` payload), at most once per `interval` and only when a new poll has landed."
+)]
+pub async fn metrics_ws(
+    State(app): State<Arc<AppState>>,
+    Query(q): Query<MetricsWsQuery>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    if !app.config.metrics_enabled {
+        return (StatusCode::NOT_FOUND, METRICS_DISABLED).into_response();
+    }
+    let every = Duration::from_secs(q.interval.unwrap_or(5).clamp(5, 300));
+    ws.on_upgrade(move |socket| send_snapshots(socket, app, every))
+        .into_response()
+}
+
+async fn send_snapshots(mut socket: WebSocket, app: Arc<AppState>, every: Duration) {
+    let mut rx = app.stats_tx.subscribe();
+    let mut ticker = interval(every);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut latest: Option<serde_json::Value> = None;
+
+    loop {
+        tokio::select! {
+            snapshot = rx.recv() => match snapshot {
+                Ok(snapshot) => latest = Some(snapshot),
+                // Only the newest snapshot matters; skip what we missed
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            _ = ticker.tick() => {
+                let Some(snapshot) = latest.take() else {
+                    continue;
+                };
+                if socket.send(Message::Text(snapshot.to_string().into())).await.is_err() {
+                    return; // client went away
+                }
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    let _ = socket.close().await;
 }
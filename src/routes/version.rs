@@ -0,0 +1,56 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::state::AppState;
+
+/// How long the daemon's answer is reused before asking again.
+const DOCKER_VERSION_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VersionResponse {
+    pub orqos: &'static str,
+    /// Docker engine version, e.g. `27.3.1`
+    pub docker: Option<String>,
+    /// Docker Engine API version, e.g. `1.47`
+    pub api_version: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    responses(
+        (status = 200, description = "Orqos and Docker versions", body = VersionResponse),
+        (status = 503, description = "Docker daemon unreachable", body = String)
+    ),
+    tag = "Health",
+)]
+pub async fn version_handler(
+    State(app): State<Arc<AppState>>,
+) -> Result<Json<VersionResponse>, (StatusCode, String)> {
+    let mut cached = app.docker_version.lock().await;
+
+    let version = match &*cached {
+        Some((at, v)) if at.elapsed() < DOCKER_VERSION_TTL => v.clone(),
+        _ => {
+            let v = app
+                .docker
+                .version()
+                .await
+                .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+            *cached = Some((Instant::now(), v.clone()));
+            v
+        }
+    };
+
+    Ok(Json(VersionResponse {
+        orqos: env!("CARGO_PKG_VERSION"),
+        docker: version.version,
+        api_version: version.api_version,
+    }))
+}
@@ -1,20 +1,42 @@
-use std::{io::Cursor, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    path::Path,
+    sync::Arc,
+};
 
 use axum::{
     extract::{Path as AxumPath, State},
     http::StatusCode,
     Json,
 };
-use bollard::{body_full, query_parameters::UploadToContainerOptions, Docker};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bollard::{
+    body_full,
+    errors::Error as BollardError,
+    query_parameters::{InspectContainerOptions, UploadToContainerOptions},
+    Docker,
+};
+use flate2::read::GzDecoder;
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tar::{Builder, Header};
 use utoipa::ToSchema;
 
 use crate::{
-    routes::exec::{exec_once_handler, ExecRequest},
+    routes::{
+        exec::{map_docker_err, run_exec_once, ExecRequest},
+        read_file::download_file,
+    },
     state::AppState,
 };
 
+lazy_static! {
+    static ref ENV_VAR_RE: Regex = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+}
+
 /// ─────────────────────────────────────────────────────────────
 /// Request/response DTOs
 /// ─────────────────────────────────────────────────────────────
@@ -22,19 +44,34 @@ use crate::{
 pub struct WriteFileRequest {
     /// **Absolute** path inside the target container
     pub path: String,
-    /// Raw UTF-8 file contents (no base64 needed)
+    /// File contents: raw UTF-8 text, or see `encoding`
     pub content: String,
+    /// `utf8` (default) or `gzip+base64` for large, compressible text; the
+    /// decompressed size is capped by `ORQOS_MAX_WRITE_BYTES` (413 above it)
+    pub encoding: Option<String>,
     /// Optional owner string, e.g. "devuser:devuser"
     pub owner: Option<String>,
     /// Optional mode string, e.g. "0644"
     pub mode: Option<String>,
     /// If true, overwrite existing file at the given path
     pub overwrite: Option<bool>,
+    /// If true, append `content` to the existing file (created if missing);
+    /// `overwrite` is ignored in this mode
+    pub append: Option<bool>,
+    /// If true, replace `${VAR}` in `content` with the container's env values
+    pub expand_env: Option<bool>,
+    /// With `expand_env`, reject unknown variables (400) instead of leaving
+    /// them literal
+    pub strict_env: Option<bool>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct WriteFileResponse {
     pub status: &'static str,
+    /// Hex SHA-256 of the bytes written
+    pub sha256: String,
+    /// Number of bytes written
+    pub size: u64,
 }
 
 #[utoipa::path(
@@ -43,8 +80,10 @@ pub struct WriteFileResponse {
     request_body = WriteFileRequest,
     responses(
         (status = 200, description = "File written successfully", body = WriteFileResponse),
-        (status = 409, description = "File exists and overwrite is false"),
-        (status = 400, description = "Invalid request"),
+        (status = 409, description = "File exists and overwrite is false (non-append writes only)"),
+        (status = 400, description = "Invalid request or undecodable content"),
+        (status = 413, description = "Request body exceeds `ORQOS_MAX_UPLOAD_BYTES`, or decompressed content exceeds `ORQOS_MAX_WRITE_BYTES`"),
+        (status = 404, description = "Container or target directory not found"),
         (status = 500, description = "Internal error"),
     ),
     params(
@@ -55,45 +94,52 @@ pub struct WriteFileResponse {
 pub async fn write_file_handler(
     State(state): State<Arc<AppState>>,
     AxumPath(container_id): AxumPath<String>,
-    Json(payload): Json<WriteFileRequest>,
+    Json(mut payload): Json<WriteFileRequest>,
 ) -> Result<Json<WriteFileResponse>, (StatusCode, String)> {
     // 0) Validate the path we got.
-    if !payload.path.starts_with('/') {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "path must be absolute (begin with '/')".into(),
-        ));
-    }
+    validate_write_path(&payload.path)?;
 
-    // Validate against path traversal
-    if payload.path.contains("/../") || payload.path.contains("/./") {
+    let append = payload.append.unwrap_or(false);
+
+    if !append
+        && payload.overwrite == Some(false)
+        && path_exists(&state, &container_id, &payload.path).await?
+    {
         return Err((
-            StatusCode::BAD_REQUEST,
-            "path contains invalid sequences".into(),
+            StatusCode::CONFLICT,
+            format!("Refusing to overwrite existing file at {}", payload.path),
         ));
     }
 
-    if payload.overwrite == Some(false) {
-        let exists_req = ExecRequest {
-            cmd: vec!["test".into(), "-e".into(), payload.path.clone()],
-            user: Some("root".into()),
-        };
+    let env = if payload.expand_env.unwrap_or(false) {
+        container_env(&state, &container_id).await?
+    } else {
+        HashMap::new()
+    };
+    let mut data = prepare_content(&mut payload, &env, state.config.max_write_bytes)?;
 
-        let exists_result = exec_once_handler(
-            axum::extract::State(state.clone()),
-            axum::extract::Path(container_id.clone()),
-            Json(exists_req),
+    if append {
+        match download_file(
+            &state.docker,
+            &container_id,
+            &payload.path,
+            state.config.max_read_bytes,
         )
-        .await;
-
-        if exists_result.is_ok() {
-            return Err((
-                StatusCode::CONFLICT,
-                format!("Refusing to overwrite existing file at {}", payload.path),
-            ));
+        .await
+        {
+            Ok(mut existing) => {
+                existing.extend_from_slice(&data);
+                data = existing;
+            }
+            // Nothing to append to yet – behave like a plain create.
+            Err((StatusCode::NOT_FOUND, _)) => {}
+            Err((sc, msg)) => return Err((sc, format!("reading file to append to: {msg}"))),
         }
     }
 
+    let sha256 = format!("{:x}", Sha256::digest(&data));
+    let size = data.len() as u64;
+
     // 1) Build an in-memory tar that contains exactly one file.
     let mut tar_bytes = Vec::<u8>::new();
     {
@@ -101,7 +147,7 @@ pub async fn write_file_handler(
 
         // Header describing the single file
         let mut header = Header::new_gnu();
-        header.set_size(payload.content.len() as u64);
+        header.set_size(size);
         header.set_mode(0o644); // regular file 0644
         header.set_cksum();
 
@@ -109,11 +155,7 @@ pub async fn write_file_handler(
         let rel_path = &payload.path[1..];
 
         builder
-            .append_data(
-                &mut header,
-                rel_path,
-                Cursor::new(payload.content.as_bytes()),
-            )
+            .append_data(&mut header, rel_path, Cursor::new(&data))
             .map_err(|e| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -147,41 +189,457 @@ pub async fn write_file_handler(
             body_full(tar_bytes.into()),
         )
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("docker cp: {e}")))?;
+        .map_err(map_upload_err)?;
 
-    // 3) Fix ownership and perms through the already-working exec_once_handler  ✅
-    //    (we wrap the extractors by hand so we can call it like a normal function)
-    use axum::extract::{Path as AxPath, State as AxState};
+    // 3) Fix ownership and perms through the already-working exec path  ✅
 
     if let Some(owner) = &payload.owner {
-        let exec_req = ExecRequest {
-            cmd: vec!["chown".into(), owner.clone(), payload.path.clone()],
-            user: Some("root".into()),
-        };
-
-        let _ = exec_once_handler(
-            AxState(state.clone()),
-            AxPath(container_id.clone()),
-            Json(exec_req),
+        run_as_root(&state, &container_id, "chown", owner, &payload.path).await?;
+    }
+
+    if let Some(mode) = &payload.mode {
+        run_as_root(&state, &container_id, "chmod", mode, &payload.path).await?;
+    }
+
+    Ok(Json(WriteFileResponse {
+        status: "ok",
+        sha256,
+        size,
+    }))
+}
+
+/// Reject relative paths and traversal sequences.
+pub(crate) fn validate_write_path(path: &str) -> Result<(), (StatusCode, String)> {
+    if !path.starts_with('/') {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "path must be absolute (begin with '/')".into(),
+        ));
+    }
+
+    // Validate against path traversal
+    if path.contains("/../") || path.contains("/./") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "path contains invalid sequences".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// `test -e` inside the container; a non-zero exit means "absent".
+pub(crate) async fn path_exists(
+    state: &AppState,
+    container_id: &str,
+    path: &str,
+) -> Result<bool, (StatusCode, String)> {
+    let exists_req = ExecRequest {
+        cmd: vec!["test".into(), "-e".into(), path.to_owned()],
+        user: Some("root".into()),
+        ..Default::default()
+    };
+
+    Ok(run_exec_once(state, container_id, &exists_req)
+        .await?
+        .exit_code
+        == 0)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WriteFileResult {
+    pub path: String,
+    /// `"ok"` or `"error"`
+    pub status: &'static str,
+    pub error: Option<String>,
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl WriteFileResult {
+    fn failed(path: String, error: impl Into<String>) -> Self {
+        Self {
+            path,
+            status: "error",
+            error: Some(error.into()),
+            sha256: None,
+            size: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WriteFilesResponse {
+    pub results: Vec<WriteFileResult>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/containers/{id}/write-files",
+    request_body = Vec<WriteFileRequest>,
+    responses(
+        (status = 200, description = "Per-file results; the upload itself succeeded", body = WriteFilesResponse),
+        (status = 400, description = "Invalid request"),
+        (status = 404, description = "Container or target directory not found"),
+        (status = 413, description = "Request body exceeds `ORQOS_MAX_UPLOAD_BYTES`"),
+        (status = 500, description = "Internal error"),
+    ),
+    params(
+        ("id" = String, Path, description = "Container ID or name")
+    ),
+    tag = "Containers",
+    description = "Writes several files with a single tar upload. Numeric `owner` (`uid:gid`) and `mode` are stored in the tar headers; symbolic owners fall back to a `chown` exec per file. `append` is not supported here."
+)]
+pub async fn write_files_handler(
+    State(state): State<Arc<AppState>>,
+    AxumPath(container_id): AxumPath<String>,
+    Json(files): Json<Vec<WriteFileRequest>>,
+) -> Result<Json<WriteFilesResponse>, (StatusCode, String)> {
+    if files.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "no files given".into()));
+    }
+
+    // One inspect covers every file that wants `${VAR}` expansion.
+    let env = if files.iter().any(|f| f.expand_env.unwrap_or(false)) {
+        container_env(&state, &container_id).await?
+    } else {
+        HashMap::new()
+    };
+
+    let mut results = Vec::with_capacity(files.len());
+    // (index into `results`, request) for every file that made it into the tar
+    let mut accepted: Vec<(usize, WriteFileRequest)> = Vec::new();
+
+    let mut tar_bytes = Vec::<u8>::new();
+    {
+        let mut builder = Builder::new(&mut tar_bytes);
+
+        for mut file in files {
+            let idx = results.len();
+
+            if let Err((_, msg)) = validate_write_path(&file.path) {
+                results.push(WriteFileResult::failed(file.path, msg));
+                continue;
+            }
+            if file.append.unwrap_or(false) {
+                results.push(WriteFileResult::failed(
+                    file.path,
+                    "append is not supported for bulk writes",
+                ));
+                continue;
+            }
+            let mode = match file.mode.as_deref().map(parse_mode) {
+                Some(None) => {
+                    results.push(WriteFileResult::failed(file.path, "invalid mode"));
+                    continue;
+                }
+                Some(Some(m)) => m,
+                None => 0o644,
+            };
+            if file.overwrite == Some(false)
+                && path_exists(&state, &container_id, &file.path).await?
+            {
+                results.push(WriteFileResult::failed(
+                    file.path.clone(),
+                    format!("Refusing to overwrite existing file at {}", file.path),
+                ));
+                continue;
+            }
+
+            let data = match prepare_content(&mut file, &env, state.config.max_write_bytes) {
+                Ok(data) => data,
+                Err((_, msg)) => {
+                    results.push(WriteFileResult::failed(file.path, msg));
+                    continue;
+                }
+            };
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(mode);
+            if let Some((uid, gid)) = file.owner.as_deref().and_then(parse_numeric_owner) {
+                header.set_uid(uid);
+                header.set_gid(gid);
+            }
+            header.set_cksum();
+
+            // Uploaded relative to `/`, so every entry keeps its full path.
+            builder
+                .append_data(&mut header, &file.path[1..], Cursor::new(&data))
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("tar build error: {e}"),
+                    )
+                })?;
+
+            results.push(WriteFileResult {
+                path: file.path.clone(),
+                status: "ok",
+                error: None,
+                sha256: Some(format!("{:x}", Sha256::digest(&data))),
+                size: Some(data.len() as u64),
+            });
+            accepted.push((idx, file));
+        }
+
+        builder.finish().map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("tar finish: {e}"),
+            )
+        })?;
+    }
+
+    if accepted.is_empty() {
+        return Ok(Json(WriteFilesResponse { results }));
+    }
+
+    state
+        .docker
+        .upload_to_container(
+            &container_id,
+            Some(UploadToContainerOptions {
+                path: "/".into(),
+                ..Default::default()
+            }),
+            body_full(tar_bytes.into()),
         )
         .await
-        .map_err(|(sc, msg)| (sc, format!("exec chown failed: {msg}")))?;
+        .map_err(map_upload_err)?;
+
+    // Owners that can't go in the tar header (user names) still need an exec.
+    for (idx, file) in accepted {
+        let Some(owner) = file.owner else { continue };
+        if parse_numeric_owner(&owner).is_some() {
+            continue;
+        }
+
+        if let Err((_, msg)) = run_as_root(&state, &container_id, "chown", &owner, &file.path).await
+        {
+            results[idx] = WriteFileResult::failed(file.path, msg);
+        }
     }
 
-    if let Some(mode) = &payload.mode {
-        let exec_req = ExecRequest {
-            cmd: vec!["chmod".into(), mode.clone(), payload.path.clone()],
-            user: Some("root".into()),
-        };
-
-        let _ = exec_once_handler(
-            AxState(state.clone()),
-            AxPath(container_id.clone()),
-            Json(exec_req),
+    Ok(Json(WriteFilesResponse { results }))
+}
+
+/// The container's configured environment as a map (`KEY=VALUE` entries).
+async fn container_env(
+    state: &AppState,
+    container_id: &str,
+) -> Result<HashMap<String, String>, (StatusCode, String)> {
+    let info = state
+        .docker
+        .inspect_container(container_id, None::<InspectContainerOptions>)
+        .await
+        .map_err(map_docker_err)?;
+
+    Ok(info
+        .config
+        .and_then(|c| c.env)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|e| e.split_once('=').map(|(k, v)| (k.to_owned(), v.to_owned())))
+        .collect())
+}
+
+/// The bytes to write for `file`: `content` decoded per `encoding`, then
+/// `${VAR}`-expanded if requested. Takes the content out of `file`.
+fn prepare_content(
+    file: &mut WriteFileRequest,
+    env: &HashMap<String, String>,
+    max_decoded: usize,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let content = std::mem::take(&mut file.content);
+    let data = decode_content(content, file.encoding.as_deref(), max_decoded)?;
+    if !file.expand_env.unwrap_or(false) {
+        return Ok(data);
+    }
+
+    let text = String::from_utf8(data).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "expand_env needs UTF-8 content".to_string(),
         )
+    })?;
+    expand_env(&text, env, file.strict_env.unwrap_or(false))
+        .map(String::into_bytes)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+/// Decode `content` per its `encoding`. Gzip output is read through a
+/// limit so a small bomb can't balloon past `max_decoded` in memory.
+fn decode_content(
+    content: String,
+    encoding: Option<&str>,
+    max_decoded: usize,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    match encoding.unwrap_or("utf8") {
+        "utf8" => Ok(content.into_bytes()),
+        "gzip+base64" => {
+            let compressed = BASE64.decode(content.trim()).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid base64 content: {e}"),
+                )
+            })?;
+
+            let mut data = Vec::new();
+            GzDecoder::new(compressed.as_slice())
+                .take(max_decoded as u64 + 1)
+                .read_to_end(&mut data)
+                .map_err(|e| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("invalid gzip content: {e}"),
+                    )
+                })?;
+            if data.len() > max_decoded {
+                return Err((
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("decompressed content exceeds {max_decoded} bytes"),
+                ));
+            }
+            Ok(data)
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!("unsupported encoding {other:?} (expected utf8 or gzip+base64)"),
+        )),
+    }
+}
+
+/// Substitute `${VAR}` placeholders from `env`. Unknown variables are left
+/// as-is unless `strict`, in which case the first one is an error.
+fn expand_env(
+    content: &str,
+    env: &HashMap<String, String>,
+    strict: bool,
+) -> Result<String, String> {
+    let mut missing = None;
+    let out = ENV_VAR_RE.replace_all(content, |caps: &regex::Captures| match env.get(&caps[1]) {
+        Some(v) => v.clone(),
+        None => {
+            missing.get_or_insert_with(|| caps[1].to_owned());
+            caps[0].to_owned()
+        }
+    });
+
+    match missing {
+        Some(var) if strict => Err(format!("unknown variable ${{{var}}} in content")),
+        _ => Ok(out.into_owned()),
+    }
+}
+
+/// Missing container (or target directory) → 404, everything else → 500.
+fn map_upload_err(err: BollardError) -> (StatusCode, String) {
+    match err {
+        BollardError::DockerResponseServerError {
+            status_code: 404,
+            message,
+        } => (StatusCode::NOT_FOUND, message),
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("docker cp: {e}")),
+    }
+}
+
+/// Run `tool arg path` (chown/chmod) as root, treating a non-zero exit as a
+/// failure and reporting the command's stderr.
+async fn run_as_root(
+    state: &AppState,
+    container_id: &str,
+    tool: &str,
+    arg: &str,
+    path: &str,
+) -> Result<(), (StatusCode, String)> {
+    let exec_req = ExecRequest {
+        cmd: vec![tool.into(), arg.into(), path.into()],
+        user: Some("root".into()),
+        ..Default::default()
+    };
+
+    let resp = run_exec_once(state, container_id, &exec_req)
         .await
-        .map_err(|(sc, msg)| (sc, format!("exec chmod failed: {msg}")))?;
+        .map_err(|(sc, msg)| (sc, format!("exec {tool} failed: {msg}")))?;
+    if resp.exit_code != 0 {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "{tool} exited with {}: {}",
+                resp.exit_code,
+                resp.stderr.trim()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Octal mode string such as `"0644"` or `"755"`.
+fn parse_mode(mode: &str) -> Option<u32> {
+    u32::from_str_radix(mode, 8).ok().filter(|m| *m <= 0o7777)
+}
+
+/// `"uid:gid"` (or bare `"uid"`) in numeric form.
+fn parse_numeric_owner(owner: &str) -> Option<(u64, u64)> {
+    let (uid, gid) = owner.split_once(':').unwrap_or((owner, owner));
+    Some((uid.parse().ok()?, gid.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn gzip_base64(data: &[u8]) -> String {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(data).unwrap();
+        BASE64.encode(enc.finish().unwrap())
     }
 
-    Ok(Json(WriteFileResponse { status: "ok" }))
+    #[test]
+    fn plain_content_is_passed_through() {
+        let data = decode_content("héllo".into(), None, 1).unwrap();
+        assert_eq!(data, "héllo".as_bytes());
+    }
+
+    #[test]
+    fn gzip_base64_round_trips() {
+        let sql = "INSERT INTO t VALUES (1);\n".repeat(1000);
+        let data =
+            decode_content(gzip_base64(sql.as_bytes()), Some("gzip+base64"), 1 << 20).unwrap();
+        assert_eq!(data, sql.as_bytes());
+    }
+
+    #[test]
+    fn decompressed_size_is_capped() {
+        let bomb = gzip_base64(&vec![0u8; 64 * 1024]);
+        let (status, _) = decode_content(bomb, Some("gzip+base64"), 1024).unwrap_err();
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let exact = gzip_base64(&[b'x'; 1024]);
+        assert_eq!(
+            decode_content(exact, Some("gzip+base64"), 1024)
+                .unwrap()
+                .len(),
+            1024
+        );
+    }
+
+    #[test]
+    fn bad_payloads_are_rejected() {
+        for (content, encoding) in [
+            ("not base64!", Some("gzip+base64")),
+            ("aGVsbG8=", Some("gzip+base64")), // base64, but not gzip
+            ("hello", Some("brotli")),
+        ] {
+            let (status, _) = decode_content(content.into(), encoding, 1024).unwrap_err();
+            assert_eq!(
+                status,
+                StatusCode::BAD_REQUEST,
+                "{content:?} / {encoding:?}"
+            );
+        }
+    }
 }
@@ -1,28 +1,46 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use bollard::{query_parameters::EventsOptionsBuilder, Docker};
+use bollard::{
+    models::{EventMessage, EventMessageTypeEnum},
+    query_parameters::EventsOptionsBuilder,
+    Docker,
+};
 use futures_util::StreamExt;
 use serde_json::Value;
 use tokio::{spawn, sync::broadcast, task::JoinHandle, time::sleep};
 
+use regex::Regex;
+
+use crate::{container_cache::ContainerListCache, redact::redact_labels};
+
 /// Spawns a background task that subscribes to Docker events and fan‑outs
 /// them through a [`broadcast::Sender`].
 ///
 /// Optimisations:
-/// * **Idle‑aware** – if there are *zero* receivers, the task parks itself and
-///   doesn’t even subscribe to Docker events, avoiding needless I/O.
+/// * **Idle‑aware** – if there are *zero* receivers and the container list
+///   cache is disabled, the task parks itself and doesn’t even subscribe to
+///   Docker events, avoiding needless I/O.
 /// * **Self‑healing** – on any stream error the task backs off exponentially
 ///   and retries.
 /// * **Log‑level sanity** – only warns when something *should* have worked.
-pub(crate) fn spawn_event_fanout(docker: Docker, tx: broadcast::Sender<Value>) -> JoinHandle<()> {
+///
+/// Container lifecycle events seen on the way through also invalidate the
+/// `GET /containers` cache. `Actor.Attributes` carry the container's labels,
+/// so sensitive ones are masked before anything is broadcast.
+pub(crate) fn spawn_event_fanout(
+    docker: Docker,
+    tx: broadcast::Sender<Value>,
+    container_cache: Arc<ContainerListCache>,
+    sensitive: Option<Regex>,
+) -> JoinHandle<()> {
     spawn(async move {
         let mut attempt: u32 = 0;
 
         tracing::debug!("Starting Docker event fan-out task");
 
         loop {
-            // If no one is listening, wait and re‑check.
-            if tx.receiver_count() == 0 {
+            // If no one is listening (WS clients or the list cache), wait and re‑check.
+            if tx.receiver_count() == 0 && !container_cache.enabled() {
                 sleep(Duration::from_secs(1)).await;
                 continue;
             }
@@ -37,15 +55,26 @@ pub(crate) fn spawn_event_fanout(docker: Docker, tx: broadcast::Sender<Value>) -
 
             while let Some(msg) = stream.next().await {
                 match msg {
-                    Ok(ev) => {
+                    Ok(mut ev) => {
                         received_any = true;
+                        if changes_container_list(&ev) {
+                            container_cache.invalidate();
+                        }
+                        if tx.receiver_count() == 0 {
+                            continue; // only the cache cares about this one
+                        }
+                        if let Some(attrs) = ev.actor.as_mut().and_then(|a| a.attributes.as_mut()) {
+                            redact_labels(sensitive.as_ref(), attrs);
+                        }
                         if let Ok(js) = serde_json::to_value(&ev) {
                             // If all receivers lag/dropped, `send` errs.
                             if let Err(err) = tx.send(js) {
                                 // If receiver count dropped to 0 mid‑flight, downgrade to debug.
                                 if tx.receiver_count() == 0 {
                                     tracing::debug!(?err, "all receivers gone; dropping events");
-                                    break; // Exit loop; will park again.
+                                    if !container_cache.enabled() {
+                                        break; // Exit loop; will park again.
+                                    }
                                 } else {
                                     tracing::warn!(
                                         ?err,
@@ -72,3 +101,22 @@ pub(crate) fn spawn_event_fanout(docker: Docker, tx: broadcast::Sender<Value>) -
         }
     })
 }
+
+/// Whether `ev` can change what `list_containers` returns.
+fn changes_container_list(ev: &EventMessage) -> bool {
+    matches!(ev.typ, Some(EventMessageTypeEnum::CONTAINER))
+        && matches!(
+            ev.action.as_deref(),
+            Some(
+                "create"
+                    | "start"
+                    | "stop"
+                    | "die"
+                    | "kill"
+                    | "destroy"
+                    | "pause"
+                    | "unpause"
+                    | "rename"
+            )
+        )
+}
@@ -1,9 +1,18 @@
+use bollard::models::SystemVersion;
 use bollard::Docker;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::broadcast;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
+use crate::audit_log::AuditLog;
+use crate::config::Config;
+use crate::container_cache::ContainerListCache;
+use crate::idempotency::IdempotencyCache;
 use crate::metric_registry::MetricRegistry;
+use crate::routes::container_create::ContainerInfo;
 
 #[derive(Clone, Copy)]
 pub struct CpuSnapshot {
@@ -11,10 +20,41 @@ pub struct CpuSnapshot {
     pub system_usage: u64,
 }
 
+/// Cumulative I/O counters from the previous poll, used to derive rates.
+#[derive(Clone, Copy)]
+pub struct IoSnapshot {
+    pub at: Instant,
+    pub net_rx: u64,
+    pub net_tx: u64,
+    pub blk_read: u64,
+    pub blk_write: u64,
+}
+
 pub struct AppState {
+    pub(crate) config: Config,
     pub(crate) docker: Docker,
     pub(crate) events_tx: broadcast::Sender<serde_json::Value>,
     pub(crate) stats_tx: broadcast::Sender<serde_json::Value>,
     pub(crate) metric_registry: MetricRegistry,
     pub(crate) cpu_snapshots: RwLock<HashMap<String, CpuSnapshot>>,
+    pub(crate) io_snapshots: RwLock<HashMap<String, IoSnapshot>>,
+    /// Docker events skipped by lagging `/events/ws` clients, summed.
+    pub(crate) events_dropped: AtomicU64,
+    pub(crate) container_cache: Arc<ContainerListCache>,
+    /// Set once the Docker daemon has answered a `version` call.
+    pub(crate) docker_ready: AtomicBool,
+    /// Last `docker version` answer, reused briefly by `GET /version`.
+    pub(crate) docker_version: Mutex<Option<(Instant, SystemVersion)>>,
+    /// Whether the last metrics poll could list containers.
+    pub(crate) last_poll_ok: AtomicBool,
+    /// When the last metrics poll ran (UNIX ms; 0 = not yet).
+    pub(crate) last_poll_time: AtomicU64,
+    /// How long the last metrics poll took, in microseconds.
+    pub(crate) last_poll_duration_us: AtomicU64,
+    /// Failed Docker calls (list, info, stats) across all metrics polls.
+    pub(crate) poll_errors: AtomicU64,
+    /// `POST /containers` results by `Idempotency-Key`.
+    pub(crate) create_idempotency: IdempotencyCache<ContainerInfo>,
+    /// Where exec sessions are recorded (`ORQOS_AUDIT_LOG`).
+    pub(crate) audit: AuditLog,
 }
@@ -2,25 +2,41 @@ use serde::Serialize;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
+use utoipa::ToSchema;
 
+use crate::metric_registry::MetricRegistry;
 use crate::state::AppState;
 
-#[derive(Debug, Serialize)]
-struct Stats {
-    cpu_avg: Option<f64>,
-    max_mem: Option<u64>,
+/// Per-container summary shared by `/stats/ws` and `GET /metrics.json`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Stats {
+    pub name: String,
+    pub cpu_avg: Option<f64>,
+    pub max_mem: Option<u64>,
 }
 
-pub fn push_stats_to_ws_clients(app: Arc<AppState>) {
-    let mut container_stats: BTreeMap<String, Stats> = BTreeMap::default();
-
-    for entry in app.metric_registry.cpu.iter() {
-        let id = entry.key();
-        let cpu_avg = app.metric_registry.cpu_avg(id, Duration::from_secs(10));
-        let max_mem = app.metric_registry.mem_max(id, Duration::from_secs(10));
+/// Summarise every container in the registry over `window`, keyed by ID.
+pub(crate) fn collect_stats(
+    registry: &MetricRegistry,
+    window: Duration,
+) -> BTreeMap<String, Stats> {
+    registry
+        .cpu
+        .iter()
+        .map(|entry| {
+            let id = entry.key();
+            let stats = Stats {
+                name: registry.display_name(id),
+                cpu_avg: registry.cpu_avg(id, window),
+                max_mem: registry.mem_max(id, window),
+            };
+            (id.clone(), stats)
+        })
+        .collect()
+}
 
-        container_stats.insert(id.clone(), Stats { cpu_avg, max_mem });
-    }
+pub fn push_stats_to_ws_clients(app: Arc<AppState>) {
+    let container_stats = collect_stats(&app.metric_registry, Duration::from_secs(10));
 
     match serde_json::to_value(&container_stats) {
         Ok(serialized) => {